@@ -1,17 +1,135 @@
+use crate::types::Type;
+
 #[derive(Debug, Clone)]
 pub enum Expr {
-    Number(f64),
+    Integer(i64),
+    Float(f64),
     String(String),
+    // `"Hello, {name}!"`: the literal pieces and embedded expressions, in order
+    Interpolation(Vec<Expr>),
+    Boolean(bool),
+    Nil,
     Identifier(String),
+    List(Vec<Expr>),
+    Map(Vec<(Expr, Expr)>),
+    // `(a, b)`; a single value needs a trailing comma, `(a,)`, to be told apart from grouping
+    Tuple(Vec<Expr>),
+    // `optional` marks the `?.[index]` form, which yields nil instead of failing
+    Index {
+        object: Box<Expr>,
+        index: Box<Expr>,
+        optional: bool,
+    },
+    Get {
+        object: Box<Expr>,
+        name: String,
+        optional: bool,
+    },
+    StructInit {
+        name: String,
+        fields: Vec<(String, Expr)>,
+    },
+    // `Shape::Circle(5)`; unit variants such as `Shape::Empty` have no arguments
+    EnumVariant {
+        enum_name: String,
+        variant: String,
+        arguments: Vec<Expr>,
+    },
+    // `if (c) { a } else { b }` in expression position, or `c ? a : b`;
+    // without an else branch the value is nil when the condition is false
+    Conditional {
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Option<Box<Expr>>,
+    },
+    Match {
+        subject: Box<Expr>,
+        arms: Vec<MatchArm<Expr>>,
+    },
+    // `{ statements... value }`: a block whose value is its optional final expression
+    Block {
+        statements: Vec<Stmt>,
+        value: Option<Box<Expr>>,
+    },
+    // `line` (here and below) is where the node starts, for the type checker's messages
     Binary {
         left: Box<Expr>,
         operator: BinaryOp,
         right: Box<Expr>,
+        line: usize,
     },
     Unary {
         operator: UnaryOp,
         operand: Box<Expr>,
+        line: usize,
+    },
+    Range {
+        start: Box<Expr>,
+        end: Box<Expr>,
+        step: Option<Box<Expr>>,
+        inclusive: bool,
+    },
+    Logical {
+        left: Box<Expr>,
+        operator: LogicalOp,
+        right: Box<Expr>,
+    },
+    Call {
+        callee: Box<Expr>,
+        arguments: Vec<Expr>,
+        line: usize,
     },
+    // an anonymous function, `fn(a) { ... }` or `|a| a + 1`; the short form's body
+    // is a single return statement
+    Lambda {
+        params: Vec<String>,
+        // one per parameter; `None` where no type was given
+        param_types: Vec<Option<Type>>,
+        return_type: Option<Type>,
+        body: Vec<Stmt>,
+        line: usize,
+    },
+}
+
+/// One `pattern if guard => body` arm. Statement-level matches have statement
+/// bodies, so arms can `return` or `break`; match expressions have expression bodies.
+#[derive(Debug, Clone)]
+pub struct MatchArm<B> {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: B,
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    // `_`
+    Wildcard,
+    // a number, string, boolean or nil literal, compared with `==`
+    Literal(Expr),
+    // binds the matched value to a name
+    Binding(String),
+    Variant {
+        enum_name: String,
+        variant: String,
+        fields: Vec<Pattern>,
+    },
+    // `(a, _, (b, c))`: a tuple of exactly this many values
+    Tuple(Vec<Pattern>),
+}
+
+impl Pattern {
+    /// Collects the names this pattern binds, in order.
+    pub fn bindings(&self, names: &mut Vec<String>) {
+        match self {
+            Pattern::Binding(name) => names.push(name.clone()),
+            Pattern::Variant { fields, .. } | Pattern::Tuple(fields) => {
+                for field in fields {
+                    field.bindings(names);
+                }
+            }
+            Pattern::Wildcard | Pattern::Literal(_) => {}
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -20,6 +138,13 @@ pub enum BinaryOp {
     Subtract,
     Multiply,
     Divide,
+    Modulo,
+    Power,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
     Equal,
     NotEqual,
     Less,
@@ -28,9 +153,43 @@ pub enum BinaryOp {
     GreaterEqual,
 }
 
+impl BinaryOp {
+    /// The operator as written in source, for messages.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Subtract => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Modulo => "%",
+            BinaryOp::Power => "**",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::ShiftLeft => "<<",
+            BinaryOp::ShiftRight => ">>",
+            BinaryOp::Equal => "==",
+            BinaryOp::NotEqual => "!=",
+            BinaryOp::Less => "<",
+            BinaryOp::Greater => ">",
+            BinaryOp::LessEqual => "<=",
+            BinaryOp::GreaterEqual => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum LogicalOp {
+    And,
+    Or,
+    // `??`: the right operand is used only when the left one is nil
+    Coalesce,
+}
+
 #[derive(Debug, Clone)]
 pub enum UnaryOp {
     Minus,
+    Not,
 }
 
 #[derive(Debug, Clone)]
@@ -38,11 +197,50 @@ pub enum Stmt {
     Expression(Expr),
     Let {
         name: String,
+        type_annotation: Option<Type>,
         value: Expr,
+        // `const` bindings cannot be assigned to or redeclared in the same scope
+        constant: bool,
+        line: usize,
+        // the `///` comment written above the declaration, if any
+        doc: Option<String>,
     },
     Assignment {
         name: String,
         value: Expr,
+        line: usize,
+    },
+    // `let (q, r) = value;` or `const (q, r) = value;`; the pattern holds only
+    // names, `_` and nested tuples
+    Destructure {
+        pattern: Pattern,
+        value: Expr,
+        constant: bool,
+        line: usize,
+    },
+    // `(a, b) = (b, a);`, which evaluates the whole value before assigning any name
+    DestructuringAssignment {
+        pattern: Pattern,
+        value: Expr,
+        line: usize,
+    },
+    IndexAssignment {
+        object: Expr,
+        index: Expr,
+        value: Expr,
+    },
+    SetField {
+        object: Expr,
+        name: String,
+        value: Expr,
+    },
+    // `target op= value`; the target is an identifier, index or field expression
+    // and is evaluated only once
+    CompoundAssignment {
+        target: Expr,
+        operator: BinaryOp,
+        value: Expr,
+        line: usize,
     },
     If {
         condition: Expr,
@@ -52,7 +250,89 @@ pub enum Stmt {
     While {
         condition: Expr,
         body: Box<Stmt>,
+        label: Option<String>,
     },
+    For {
+        var: String,
+        iterable: Expr,
+        body: Box<Stmt>,
+        label: Option<String>,
+    },
+    Break(Option<String>),
+    Continue(Option<String>),
     Block(Vec<Stmt>),
     Print(Expr),
+    Function {
+        name: String,
+        params: Vec<String>,
+        param_types: Vec<Option<Type>>,
+        return_type: Option<Type>,
+        body: Vec<Stmt>,
+        doc: Option<String>,
+        line: usize,
+    },
+    Return {
+        value: Option<Expr>,
+        line: usize,
+    },
+    // hands `value` to whoever is consuming the generator and suspends until the next is wanted
+    Yield {
+        value: Expr,
+        line: usize,
+    },
+    Struct {
+        name: String,
+        fields: Vec<String>,
+        doc: Option<String>,
+        line: usize,
+    },
+    // each variant lists the names of its payload fields
+    Enum {
+        name: String,
+        variants: Vec<(String, Vec<String>)>,
+        doc: Option<String>,
+        line: usize,
+    },
+    Match {
+        subject: Expr,
+        arms: Vec<MatchArm<Stmt>>,
+    },
+    // binds the module loaded from `path` to `name`
+    Import {
+        path: String,
+        name: String,
+        line: usize,
+    },
+    Throw(Expr),
+    // at least one of `catch` and `finally` is present; `catch` holds the name
+    // the caught value is bound to and the handler
+    Try {
+        body: Vec<Stmt>,
+        catch: Option<(String, Vec<Stmt>)>,
+        finally: Option<Vec<Stmt>>,
+    },
+}
+
+impl Stmt {
+    /// Whether running this statement can reach a `yield`, which makes the
+    /// function containing it a generator. Nested functions are not searched,
+    /// since their yields belong to them.
+    pub fn contains_yield(&self) -> bool {
+        let any = |statements: &[Stmt]| statements.iter().any(Stmt::contains_yield);
+        match self {
+            Stmt::Yield { .. } => true,
+            Stmt::If { then_stmt, else_stmt, .. } => {
+                then_stmt.contains_yield() || else_stmt.as_ref().is_some_and(|stmt| stmt.contains_yield())
+            }
+            Stmt::While { body, .. } | Stmt::For { body, .. } => body.contains_yield(),
+            Stmt::Block(statements) => any(statements),
+            Stmt::Match { arms, .. } => arms.iter().any(|arm| arm.body.contains_yield()),
+            Stmt::Try { body, catch, finally } => {
+                any(body)
+                    || catch.as_ref().is_some_and(|(_, handler)| any(handler))
+                    || finally.as_ref().is_some_and(|finally| any(finally))
+            }
+            _ => false,
+        }
+    }
 }
\ No newline at end of file
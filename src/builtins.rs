@@ -0,0 +1,81 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::environment::Environment;
+use crate::interpreter::{Interpreter, RuntimeError};
+use crate::value::{MapKey, NativeFn, NativeFunction, Value};
+
+const BUILTINS: &[NativeFunction] = &[
+    NativeFunction { name: "len", arity: 1, function: NativeFn::Plain(len) },
+    NativeFunction { name: "push", arity: 2, function: NativeFn::Plain(push) },
+    NativeFunction { name: "has", arity: 2, function: NativeFn::Plain(has) },
+    NativeFunction { name: "keys", arity: 1, function: NativeFn::Plain(keys) },
+    NativeFunction { name: "values", arity: 1, function: NativeFn::Plain(values) },
+    NativeFunction { name: "next", arity: 1, function: NativeFn::WithInterpreter(next) },
+];
+
+/// Binds every built-in function in `environment`.
+pub fn define_builtins(environment: &mut Environment) {
+    for native in BUILTINS {
+        environment.define(native.name.to_string(), Value::NativeFunction(*native));
+    }
+}
+
+fn len(args: Vec<Value>) -> Result<Value, String> {
+    match &args[0] {
+        Value::List(elements) => Ok(Value::Int(elements.borrow().len() as i64)),
+        Value::Map(entries) => Ok(Value::Int(entries.borrow().len() as i64)),
+        Value::Tuple(values) => Ok(Value::Int(values.len() as i64)),
+        Value::String(s) => Ok(Value::Int(s.chars().count() as i64)),
+        other => Err(format!("len() expects a list, map, tuple or string, got '{}'", other)),
+    }
+}
+
+fn push(args: Vec<Value>) -> Result<Value, String> {
+    let mut args = args.into_iter();
+    match (args.next(), args.next()) {
+        (Some(Value::List(elements)), Some(value)) => {
+            elements.borrow_mut().push(value);
+            Ok(Value::Int(elements.borrow().len() as i64))
+        }
+        (Some(other), _) => Err(format!("push() expects a list, got '{}'", other)),
+        _ => unreachable!("arity is checked before natives are called"),
+    }
+}
+
+fn has(args: Vec<Value>) -> Result<Value, String> {
+    match &args[0] {
+        Value::Map(entries) => {
+            let key = MapKey::from_value(&args[1])?;
+            Ok(Value::Boolean(entries.borrow().contains_key(&key)))
+        }
+        other => Err(format!("has() expects a map, got '{}'", other)),
+    }
+}
+
+fn keys(args: Vec<Value>) -> Result<Value, String> {
+    match &args[0] {
+        Value::Map(entries) => {
+            let keys = entries.borrow().keys().map(MapKey::to_value).collect();
+            Ok(Value::List(Rc::new(RefCell::new(keys))))
+        }
+        other => Err(format!("keys() expects a map, got '{}'", other)),
+    }
+}
+
+fn values(args: Vec<Value>) -> Result<Value, String> {
+    match &args[0] {
+        Value::Map(entries) => {
+            let values = entries.borrow().values().cloned().collect();
+            Ok(Value::List(Rc::new(RefCell::new(values))))
+        }
+        other => Err(format!("values() expects a map, got '{}'", other)),
+    }
+}
+
+/// The next value `args[0]` yields, or `nil` once it has finished.
+fn next(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    match &args[0] {
+        Value::Generator(generator) => Ok(interpreter.resume(generator)?.unwrap_or(Value::Nil)),
+        other => Err(format!("next() expects a generator, got '{}'", other).into()),
+    }
+}
@@ -0,0 +1,76 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use crate::value::Value;
+
+/// A single lexical scope. Lookups and assignments that miss in this scope
+/// walk outward through `enclosing` until the global scope is reached.
+#[derive(Default)]
+pub struct Environment {
+    values: HashMap<String, Value>,
+    // names in `values` bound with `const`
+    constants: HashSet<String>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            values: HashMap::new(),
+            constants: HashSet::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+    
+    /// Binds `name` in this scope, shadowing any binding of the same name in
+    /// an outer scope (or replacing an earlier one in this scope).
+    pub fn define(&mut self, name: String, value: Value) {
+        self.constants.remove(&name);
+        self.values.insert(name, value);
+    }
+    
+    /// Like `define`, but the binding cannot be assigned to afterwards.
+    pub fn define_constant(&mut self, name: String, value: Value) {
+        self.constants.insert(name.clone());
+        self.values.insert(name, value);
+    }
+    
+    /// Whether the nearest binding of `name` is a constant.
+    pub fn is_constant(&self, name: &str) -> bool {
+        if self.values.contains_key(name) {
+            return self.constants.contains(name);
+        }
+        self.enclosing
+            .as_ref()
+            .is_some_and(|enclosing| enclosing.borrow().is_constant(name))
+    }
+    
+    pub fn get(&self, name: &str) -> Option<Value> {
+        match self.values.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.enclosing.as_ref()?.borrow().get(name),
+        }
+    }
+    
+    /// Looks `name` up in this scope only.
+    pub fn get_local(&self, name: &str) -> Option<Value> {
+        self.values.get(name).cloned()
+    }
+    
+    /// Updates the nearest existing binding of `name`. Returns `false` if no
+    /// scope in the chain defines it.
+    pub fn assign(&mut self, name: &str, value: Value) -> bool {
+        if let Some(slot) = self.values.get_mut(name) {
+            *slot = value;
+            return true;
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => false,
+        }
+    }
+}
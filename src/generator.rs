@@ -0,0 +1,107 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use crate::ast::{Expr, Stmt};
+use crate::environment::Environment;
+use crate::interpreter::{ControlFlow, Iteration, RuntimeError};
+use crate::value::Value;
+
+/// A call to a generator function, suspended between the values it yields.
+pub struct Generator {
+    pub name: Option<String>,
+    // what is left to run, innermost last; empty once the generator has finished
+    pub frames: Vec<Frame>,
+    // set while its code runs, so that the code cannot resume it again
+    pub running: bool,
+}
+
+impl Generator {
+    /// The name used in error messages.
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or("<fn>")
+    }
+}
+
+impl fmt::Debug for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Generator")
+            .field("name", &self.name)
+            .field("finished", &self.frames.is_empty())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Where a suspended generator is within one statement of its body. A
+/// generator keeps a stack of these, innermost last, in place of the Rust
+/// calls `execute_stmt` would make, so that it can stop at a `yield` and carry
+/// on from there later.
+pub enum Frame {
+    // running `statements` in `scope`; `next` is the index of the next one
+    Block { statements: Rc<[Stmt]>, next: usize, scope: Rc<RefCell<Environment>> },
+    // loops run their body in a block frame above them; back on top, they
+    // start the next pass or finish
+    While {
+        condition: Expr,
+        body: Rc<[Stmt]>,
+        label: Option<String>,
+        scope: Rc<RefCell<Environment>>,
+    },
+    For {
+        var: String,
+        iteration: Iteration,
+        body: Rc<[Stmt]>,
+        label: Option<String>,
+        scope: Rc<RefCell<Environment>>,
+    },
+    Try {
+        body: Rc<[Stmt]>,
+        catch: Option<(String, Rc<[Stmt]>)>,
+        finally: Option<Rc<[Stmt]>>,
+        stage: TryStage,
+        scope: Rc<RefCell<Environment>>,
+    },
+}
+
+impl Frame {
+    /// A frame running `statements` in a new scope inside `enclosing`.
+    pub fn block(statements: &Rc<[Stmt]>, enclosing: &Rc<RefCell<Environment>>) -> Self {
+        Frame::Block {
+            statements: Rc::clone(statements),
+            next: 0,
+            scope: Rc::new(RefCell::new(Environment::with_enclosing(Rc::clone(enclosing)))),
+        }
+    }
+}
+
+/// The part of a `try` statement a generator is in.
+pub enum TryStage {
+    Start,
+    Body,
+    Handler,
+    // running `finally`, after which the try is left the way it was being left, if any
+    Finally(Option<Exit>),
+}
+
+/// How a generator leaves statements before finishing them.
+pub enum Exit {
+    Flow(ControlFlow),
+    Error(RuntimeError),
+}
+
+/// What a generator does after advancing its innermost frame.
+pub enum Step {
+    Next,
+    Enter(Frame),
+    Leave,
+    Yield(Value),
+    Exit(Exit),
+}
+
+/// The statements of a loop or branch body, shared so that each pass can run
+/// them without copying.
+pub fn body_statements(body: &Stmt) -> Rc<[Stmt]> {
+    match body {
+        Stmt::Block(statements) => Rc::from(statements.as_slice()),
+        other => Rc::from(std::slice::from_ref(other)),
+    }
+}
@@ -0,0 +1,1184 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast::{BinaryOp, Expr, LogicalOp, MatchArm, Pattern, Stmt, UnaryOp};
+use crate::types::Type;
+
+/// The result of inferring types for a whole program.
+pub struct Inference {
+    /// `line N: name: type` for every `let`, `const` and `fn`, in source order.
+    pub bindings: Vec<String>,
+    /// Contradictions between the ways values are used, in line order.
+    pub errors: Vec<String>,
+}
+
+/// Infers a type for every binding and expression in `statements` without
+/// running them, Hindley–Milner style: each variable has a single type, worked
+/// out from how it is initialised, assigned and used, and functions declared
+/// with `fn` are generic in whatever their bodies leave open.
+///
+/// `nil` is accepted wherever any type is expected, as it is by the type
+/// checker. A value that may be either an int or a float, such as an element of
+/// `[1, 2.5]`, is a `num`. Lists and maps whose literals mix other element
+/// types have elements of type `any`, which is not checked.
+pub fn infer(statements: &[Stmt]) -> Inference {
+    let mut inferer = Inferer::new();
+    inferer.infer_block(statements);
+    
+    let mut bindings = std::mem::take(&mut inferer.bindings);
+    bindings.sort_by_key(|(line, _, _)| *line);
+    inferer.errors.sort_by_key(|(line, _)| *line);
+    
+    // unknown types are named across the whole listing, so that one name is one type
+    let mut names = HashMap::new();
+    Inference {
+        bindings: bindings
+            .into_iter()
+            .map(|(line, name, ty)| {
+                format!("line {}: {}: {}", line, name, inferer.display_with(&inferer.zonk(&ty), &mut names))
+            })
+            .collect(),
+        errors: inferer.errors
+            .into_iter()
+            .map(|(line, message)| format!("{} at line {}", message, line))
+            .collect(),
+    }
+}
+
+/// A type during inference. Variables stand for types that are not known yet
+/// and are bound as uses of the value are found.
+#[derive(Debug, Clone, PartialEq)]
+enum Ty {
+    Var(usize),
+    Int,
+    Float,
+    // an int or a float, from a `num` annotation
+    Num,
+    Str,
+    Bool,
+    Nil,
+    Range,
+    List(Box<Ty>),
+    Map(Box<Ty>, Box<Ty>),
+    Fn(Vec<Ty>, Box<Ty>),
+    Tuple(Vec<Ty>),
+    // yielding values of the given type
+    Generator(Box<Ty>),
+    // a struct or enum, by name
+    Named(String),
+    // a value whose type varies, such as an element of `[1, "one"]`
+    Any,
+}
+
+impl Ty {
+    fn is_numeric(&self) -> bool {
+        matches!(self, Ty::Int | Ty::Float | Ty::Num)
+    }
+}
+
+/// A type whose `vars` are instantiated afresh at each use, so that a generic
+/// function can be called with different types.
+#[derive(Clone)]
+struct Scheme {
+    vars: Vec<usize>,
+    ty: Ty,
+}
+
+impl Scheme {
+    fn mono(ty: Ty) -> Self {
+        Scheme { vars: Vec::new(), ty }
+    }
+}
+
+struct Inferer {
+    // what each type variable is bound to, if anything yet
+    substitution: Vec<Option<Ty>>,
+    // variables from `fn` annotations, which may only be bound to function types
+    callables: HashSet<usize>,
+    // innermost scope last
+    scopes: Vec<HashMap<String, Scheme>>,
+    // struct field types by struct and field name
+    fields: HashMap<(String, String), Ty>,
+    // enum payload types by enum and variant name
+    variants: HashMap<(String, String), Vec<Ty>>,
+    // return types of the enclosing functions, innermost last
+    return_types: Vec<Ty>,
+    // types yielded by the enclosing generator functions, innermost last
+    yield_types: Vec<Ty>,
+    // line of the innermost node being inferred that records one
+    line: usize,
+    bindings: Vec<(usize, String, Ty)>,
+    errors: Vec<(usize, String)>,
+}
+
+impl Inferer {
+    fn new() -> Self {
+        let mut inferer = Self {
+            substitution: Vec::new(),
+            callables: HashSet::new(),
+            scopes: vec![HashMap::new()],
+            fields: HashMap::new(),
+            variants: HashMap::new(),
+            return_types: Vec::new(),
+            yield_types: Vec::new(),
+            line: 0,
+            bindings: Vec::new(),
+            errors: Vec::new(),
+        };
+        inferer.define_builtins();
+        inferer
+    }
+    
+    fn define_builtins(&mut self) {
+        let (a, b) = (self.fresh(), self.fresh());
+        let (k, v) = (self.fresh(), self.fresh());
+        let builtins = [
+            ("len", Ty::Fn(vec![a.clone()], Box::new(Ty::Int))),
+            ("push", Ty::Fn(vec![Ty::List(Box::new(a.clone())), a], Box::new(Ty::Int))),
+            ("has", Ty::Fn(vec![map(&k, &v), k.clone()], Box::new(Ty::Bool))),
+            ("keys", Ty::Fn(vec![map(&k, &v)], Box::new(Ty::List(Box::new(k.clone()))))),
+            ("values", Ty::Fn(vec![map(&k, &v)], Box::new(Ty::List(Box::new(v.clone()))))),
+            ("next", Ty::Fn(vec![Ty::Generator(Box::new(b.clone()))], Box::new(b))),
+        ];
+        for (name, ty) in builtins {
+            let scheme = self.generalize(&ty);
+            self.define(name, scheme);
+        }
+        
+        self.fields.insert(("Error".to_string(), "kind".to_string()), Ty::Str);
+        self.fields.insert(("Error".to_string(), "message".to_string()), Ty::Str);
+    }
+    
+    fn fresh(&mut self) -> Ty {
+        self.substitution.push(None);
+        Ty::Var(self.substitution.len() - 1)
+    }
+    
+    fn error(&mut self, message: String) {
+        self.errors.push((self.line, message));
+    }
+    
+    // --- unification ---
+    
+    /// Follows variable bindings until reaching a type that is not a bound variable.
+    fn resolve(&self, ty: &Ty) -> Ty {
+        match ty {
+            Ty::Var(var) => match &self.substitution[*var] {
+                Some(bound) => self.resolve(bound),
+                None => ty.clone(),
+            },
+            _ => ty.clone(),
+        }
+    }
+    
+    /// `ty` with every bound variable inside it replaced by what it is bound to.
+    fn zonk(&self, ty: &Ty) -> Ty {
+        match self.resolve(ty) {
+            Ty::List(element) => Ty::List(Box::new(self.zonk(&element))),
+            Ty::Generator(element) => Ty::Generator(Box::new(self.zonk(&element))),
+            Ty::Map(key, value) => Ty::Map(Box::new(self.zonk(&key)), Box::new(self.zonk(&value))),
+            Ty::Fn(params, returns) => Ty::Fn(
+                params.iter().map(|param| self.zonk(param)).collect(),
+                Box::new(self.zonk(&returns)),
+            ),
+            Ty::Tuple(elements) => Ty::Tuple(elements.iter().map(|element| self.zonk(element)).collect()),
+            other => other,
+        }
+    }
+    
+    fn occurs(&self, var: usize, ty: &Ty) -> bool {
+        match self.resolve(ty) {
+            Ty::Var(other) => other == var,
+            Ty::List(element) | Ty::Generator(element) => self.occurs(var, &element),
+            Ty::Map(key, value) => self.occurs(var, &key) || self.occurs(var, &value),
+            Ty::Fn(params, returns) => params.iter().any(|param| self.occurs(var, param)) || self.occurs(var, &returns),
+            Ty::Tuple(elements) => elements.iter().any(|element| self.occurs(var, element)),
+            _ => false,
+        }
+    }
+    
+    /// Makes `a` and `b` the same type, binding variables as needed. Fails if
+    /// they are incompatible; bindings made before the failure are kept.
+    fn unify(&mut self, a: &Ty, b: &Ty) -> Result<(), ()> {
+        match (self.resolve(a), self.resolve(b)) {
+            // nil and `any` fit any type, without saying anything about it
+            (Ty::Nil | Ty::Any, _) | (_, Ty::Nil | Ty::Any) => Ok(()),
+            (Ty::Var(a), Ty::Var(b)) if a == b => Ok(()),
+            (Ty::Var(var), ty) | (ty, Ty::Var(var)) => {
+                if self.occurs(var, &ty) {
+                    return Err(());
+                }
+                if self.callables.contains(&var) {
+                    match ty {
+                        Ty::Var(other) => {
+                            self.callables.insert(other);
+                        }
+                        Ty::Fn(..) => {}
+                        _ => return Err(()),
+                    }
+                }
+                self.substitution[var] = Some(ty);
+                Ok(())
+            }
+            (Ty::List(a), Ty::List(b)) | (Ty::Generator(a), Ty::Generator(b)) => self.unify(&a, &b),
+            (Ty::Map(a_key, a_value), Ty::Map(b_key, b_value)) => {
+                self.unify(&a_key, &b_key)?;
+                self.unify(&a_value, &b_value)
+            }
+            (Ty::Fn(a_params, a_returns), Ty::Fn(b_params, b_returns)) if a_params.len() == b_params.len() => {
+                for (a, b) in a_params.iter().zip(&b_params) {
+                    self.unify(a, b)?;
+                }
+                self.unify(&a_returns, &b_returns)
+            }
+            (Ty::Tuple(a), Ty::Tuple(b)) if a.len() == b.len() => {
+                for (a, b) in a.iter().zip(&b) {
+                    self.unify(a, b)?;
+                }
+                Ok(())
+            }
+            // a `num` may hold either kind of number
+            (Ty::Num, number) | (number, Ty::Num) if number.is_numeric() => Ok(()),
+            (a, b) if a == b => Ok(()),
+            _ => Err(()),
+        }
+    }
+    
+    /// The type of a value that comes from either `a` or `b`, such as the
+    /// branches of a conditional: a `num` if they are different kinds of
+    /// number, or one is a number and the other unknown, since nothing converts
+    /// one kind into the other; or else both unified. `None` if they cannot be.
+    fn join(&mut self, a: &Ty, b: &Ty) -> Option<Ty> {
+        match (self.resolve(a), self.resolve(b)) {
+            (a, b) if a.is_numeric() && b.is_numeric() && a != b => Some(Ty::Num),
+            (Ty::Var(var), number) | (number, Ty::Var(var)) if number.is_numeric() => {
+                self.unify(&Ty::Var(var), &Ty::Num).ok().map(|_| Ty::Num)
+            }
+            _ => self.unify(a, b).ok().map(|_| a.clone()),
+        }
+    }
+    
+    /// Joins `ty` into the element type of a literal so far, if any; elements
+    /// that cannot be joined make it `any`.
+    fn join_into(&mut self, so_far: Option<Ty>, ty: &Ty) -> Ty {
+        match so_far {
+            Some(so_far) => self.join(&so_far, ty).unwrap_or(Ty::Any),
+            None => ty.clone(),
+        }
+    }
+    
+    /// Rebinds the variable `ty` resolves through to `num` if it is bound to one
+    /// kind of number and `value` is the other, as when an unannotated function
+    /// returns an int in one place and a float in another. Whether it did.
+    fn widen(&mut self, ty: &Ty, value: &Ty) -> bool {
+        let Ty::Var(mut var) = ty else {
+            return false;
+        };
+        while let Some(Ty::Var(next)) = &self.substitution[var] {
+            var = *next;
+        }
+        match (&self.substitution[var], self.resolve(value)) {
+            (Some(bound), value) if bound.is_numeric() && value.is_numeric() && *bound != value => {
+                self.substitution[var] = Some(Ty::Num);
+                true
+            }
+            _ => false,
+        }
+    }
+    
+    /// Unifies, reporting a mismatch for `what` on failure.
+    fn expect(&mut self, expected: &Ty, actual: &Ty, what: impl FnOnce() -> String) {
+        if self.unify(expected, actual).is_err() {
+            let message = format!(
+                "Type mismatch for {}: expected {}, found {}",
+                what(),
+                self.display(expected),
+                self.display(actual)
+            );
+            self.error(message);
+        }
+    }
+    
+    // --- schemes and scopes ---
+    
+    fn free_vars(&self, ty: &Ty, vars: &mut Vec<usize>) {
+        match self.resolve(ty) {
+            Ty::Var(var) if !vars.contains(&var) => vars.push(var),
+            Ty::List(element) | Ty::Generator(element) => self.free_vars(&element, vars),
+            Ty::Map(key, value) => {
+                self.free_vars(&key, vars);
+                self.free_vars(&value, vars);
+            }
+            Ty::Fn(params, returns) => {
+                for param in &params {
+                    self.free_vars(param, vars);
+                }
+                self.free_vars(&returns, vars);
+            }
+            Ty::Tuple(elements) => {
+                for element in &elements {
+                    self.free_vars(element, vars);
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    /// Quantifies the variables of `ty` that no binding in scope depends on.
+    fn generalize(&self, ty: &Ty) -> Scheme {
+        let mut in_scope = Vec::new();
+        for scheme in self.scopes.iter().flat_map(HashMap::values) {
+            let mut vars = Vec::new();
+            self.free_vars(&scheme.ty, &mut vars);
+            in_scope.extend(vars.into_iter().filter(|var| !scheme.vars.contains(var)));
+        }
+        let in_scope: HashSet<usize> = in_scope.into_iter().collect();
+        
+        let mut vars = Vec::new();
+        self.free_vars(ty, &mut vars);
+        vars.retain(|var| !in_scope.contains(var));
+        Scheme { vars, ty: self.zonk(ty) }
+    }
+    
+    fn instantiate(&mut self, scheme: &Scheme) -> Ty {
+        let mut fresh = HashMap::new();
+        for var in &scheme.vars {
+            let ty = self.fresh();
+            if self.callables.contains(var) {
+                self.callables.insert(self.substitution.len() - 1);
+            }
+            fresh.insert(*var, ty);
+        }
+        substitute(&scheme.ty, &fresh)
+    }
+    
+    fn define(&mut self, name: &str, scheme: Scheme) {
+        self.scopes
+            .last_mut()
+            .expect("the global scope is never popped")
+            .insert(name.to_string(), scheme);
+    }
+    
+    fn lookup(&self, name: &str) -> Option<Scheme> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name)).cloned()
+    }
+    
+    /// Runs `infer` in a new scope holding `bindings`.
+    fn in_scope<T>(&mut self, bindings: Vec<(String, Ty)>, infer: impl FnOnce(&mut Self) -> T) -> T {
+        self.scopes.push(bindings.into_iter().map(|(name, ty)| (name, Scheme::mono(ty))).collect());
+        let result = infer(self);
+        self.scopes.pop();
+        result
+    }
+    
+    /// The type an annotation stands for. `fn` does not say how many
+    /// parameters there are, so it is a variable that only a function type fits.
+    fn annotation(&mut self, annotation: &Option<Type>) -> Ty {
+        match annotation {
+            Some(Type::Int) => Ty::Int,
+            Some(Type::Float) => Ty::Float,
+            Some(Type::Str) => Ty::Str,
+            Some(Type::Bool) => Ty::Bool,
+            Some(Type::Nil) => Ty::Nil,
+            Some(Type::Range) => Ty::Range,
+            Some(Type::List) => Ty::List(Box::new(self.fresh())),
+            Some(Type::Map) => Ty::Map(Box::new(self.fresh()), Box::new(self.fresh())),
+            Some(Type::Generator) => Ty::Generator(Box::new(self.fresh())),
+            // the annotation does not say how many values
+            Some(Type::Tuple) => self.fresh(),
+            Some(Type::Named(name)) => Ty::Named(name.clone()),
+            Some(Type::Num) => Ty::Num,
+            Some(Type::Any) => Ty::Any,
+            Some(Type::Fn) => {
+                let ty = self.fresh();
+                self.callables.insert(self.substitution.len() - 1);
+                ty
+            }
+            None => self.fresh(),
+        }
+    }
+    
+    fn field_type(&mut self, def: &str, field: &str) -> Ty {
+        let key = (def.to_string(), field.to_string());
+        if let Some(ty) = self.fields.get(&key) {
+            return ty.clone();
+        }
+        let ty = self.fresh();
+        self.fields.insert(key, ty.clone());
+        ty
+    }
+    
+    fn variant_types(&mut self, enum_name: &str, variant: &str, count: usize) -> Vec<Ty> {
+        let key = (enum_name.to_string(), variant.to_string());
+        if let Some(types) = self.variants.get(&key) {
+            return types.clone();
+        }
+        let types: Vec<Ty> = (0..count).map(|_| self.fresh()).collect();
+        self.variants.insert(key, types.clone());
+        types
+    }
+    
+    // --- statements ---
+    
+    fn infer_block(&mut self, statements: &[Stmt]) {
+        for stmt in statements {
+            self.infer_stmt(stmt);
+        }
+    }
+    
+    fn infer_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression(expr) | Stmt::Print(expr) | Stmt::Throw(expr) => {
+                self.infer_expr(expr);
+            }
+            Stmt::Let { name, type_annotation, value, line, .. } => {
+                self.line = *line;
+                let ty = self.annotation(type_annotation);
+                let value_ty = self.infer_expr(value);
+                self.line = *line;
+                self.expect(&ty, &value_ty, || format!("variable '{}'", name));
+                // only a function or another name is generic: a list or map built
+                // here is one value, whose element type later uses must agree on
+                let scheme = match value {
+                    Expr::Lambda { .. } | Expr::Identifier(_) => self.generalize(&ty),
+                    _ => Scheme::mono(ty.clone()),
+                };
+                self.define(name, scheme);
+                self.bindings.push((*line, name.clone(), ty));
+            }
+            Stmt::Assignment { name, value, line } => {
+                self.line = *line;
+                let value = self.infer_expr(value);
+                self.line = *line;
+                if let Some(scheme) = self.lookup(name) {
+                    let ty = self.instantiate(&scheme);
+                    self.expect(&ty, &value, || format!("variable '{}'", name));
+                }
+            }
+            Stmt::Destructure { pattern, value, line, .. } => {
+                self.line = *line;
+                let value = self.infer_expr(value);
+                self.line = *line;
+                let mut names = Vec::new();
+                self.destructure(pattern, &value, &mut names);
+                for (name, ty) in names {
+                    self.define(&name, Scheme::mono(ty.clone()));
+                    self.bindings.push((*line, name, ty));
+                }
+            }
+            Stmt::DestructuringAssignment { pattern, value, line } => {
+                self.line = *line;
+                let value = self.infer_expr(value);
+                self.line = *line;
+                let mut names = Vec::new();
+                self.destructure(pattern, &value, &mut names);
+                for (name, value) in names {
+                    if let Some(scheme) = self.lookup(&name) {
+                        let ty = self.instantiate(&scheme);
+                        self.expect(&ty, &value, || format!("variable '{}'", name));
+                    }
+                }
+            }
+            Stmt::CompoundAssignment { target, operator, value, line } => {
+                self.line = *line;
+                let current = self.infer_expr(target);
+                let rhs = self.infer_expr(value);
+                self.line = *line;
+                let result = self.binary_type(&current, operator, &rhs);
+                let what = match target {
+                    Expr::Identifier(name) => format!("variable '{}'", name),
+                    _ => "assignment target".to_string(),
+                };
+                self.expect(&current, &result, || what);
+            }
+            Stmt::IndexAssignment { object, index, value } => {
+                let object = self.infer_expr(object);
+                let index = self.infer_expr(index);
+                let value = self.infer_expr(value);
+                let element = self.index_type(&object, &index);
+                self.expect(&element, &value, || "element".to_string());
+            }
+            Stmt::SetField { object, name, value } => {
+                let object = self.infer_expr(object);
+                let value = self.infer_expr(value);
+                let field = self.member_type(&object, name);
+                self.expect(&field, &value, || format!("field '{}'", name));
+            }
+            Stmt::If { condition, then_stmt, else_stmt } => {
+                self.infer_expr(condition);
+                self.infer_stmt(then_stmt);
+                if let Some(else_stmt) = else_stmt {
+                    self.infer_stmt(else_stmt);
+                }
+            }
+            Stmt::While { condition, body, .. } => {
+                self.infer_expr(condition);
+                self.infer_stmt(body);
+            }
+            Stmt::For { var, iterable, body, .. } => {
+                let iterable = self.infer_expr(iterable);
+                let item = match self.resolve(&iterable) {
+                    Ty::Range => Ty::Int,
+                    Ty::Str => Ty::Str,
+                    Ty::List(element) | Ty::Generator(element) => *element,
+                    Ty::Map(key, _) => *key,
+                    Ty::Tuple(_) | Ty::Var(_) | Ty::Nil => self.fresh(),
+                    Ty::Any => Ty::Any,
+                    other => {
+                        let message = format!("Cannot iterate over {}", self.display(&other));
+                        self.error(message);
+                        self.fresh()
+                    }
+                };
+                self.in_scope(vec![(var.clone(), item)], |inferer| inferer.infer_stmt(body));
+            }
+            Stmt::Break(_) | Stmt::Continue(_) => {}
+            Stmt::Block(statements) => self.in_scope(Vec::new(), |inferer| inferer.infer_block(statements)),
+            Stmt::Function { name, params, param_types, return_type, body, line, .. } => {
+                self.line = *line;
+                let param_types: Vec<Ty> = param_types.iter().map(|ty| self.annotation(ty)).collect();
+                let returns = self.annotation(return_type);
+                let ty = Ty::Fn(param_types.clone(), Box::new(returns.clone()));
+                
+                // recursive calls see the function at a single type; other code sees it generalised
+                self.define(name, Scheme::mono(ty.clone()));
+                self.infer_function(params, param_types, returns, body);
+                self.scopes.last_mut().expect("the global scope is never popped").remove(name);
+                let scheme = self.generalize(&ty);
+                self.define(name, scheme);
+                self.bindings.push((*line, name.clone(), ty));
+            }
+            Stmt::Return { value, line } => {
+                self.line = *line;
+                let value = match value {
+                    Some(value) => self.infer_expr(value),
+                    None => Ty::Nil,
+                };
+                self.line = *line;
+                if let Some(returns) = self.return_types.last().cloned() {
+                    if !self.widen(&returns, &value) {
+                        self.expect(&returns, &value, || "return value".to_string());
+                    }
+                }
+            }
+            Stmt::Yield { value, line } => {
+                self.line = *line;
+                let value = self.infer_expr(value);
+                self.line = *line;
+                if let Some(element) = self.yield_types.last().cloned() {
+                    self.expect(&element, &value, || "yielded value".to_string());
+                }
+            }
+            Stmt::Struct { name, .. } | Stmt::Enum { name, .. } | Stmt::Import { name, .. } => {
+                let ty = self.fresh();
+                self.define(name, Scheme::mono(ty));
+            }
+            Stmt::Match { subject, arms } => {
+                let subject = self.infer_expr(subject);
+                self.infer_arms(&subject, arms, |inferer, body| inferer.infer_stmt(body));
+            }
+            Stmt::Try { body, catch, finally } => {
+                self.in_scope(Vec::new(), |inferer| inferer.infer_block(body));
+                if let Some((name, handler)) = catch {
+                    let caught = self.fresh();
+                    self.in_scope(vec![(name.clone(), caught)], |inferer| inferer.infer_block(handler));
+                }
+                if let Some(finally) = finally {
+                    self.in_scope(Vec::new(), |inferer| inferer.infer_block(finally));
+                }
+            }
+        }
+    }
+    
+    fn infer_function(&mut self, params: &[String], param_types: Vec<Ty>, returns: Ty, body: &[Stmt]) {
+        let bindings = params.iter().cloned().zip(param_types).collect();
+        if !body.iter().any(Stmt::contains_yield) {
+            self.return_types.push(returns);
+            self.in_scope(bindings, |inferer| inferer.infer_block(body));
+            self.return_types.pop();
+            return;
+        }
+        
+        // calling a generator function gives a generator; `return` only stops it
+        let element = self.fresh();
+        self.expect(&returns, &Ty::Generator(Box::new(element.clone())), || "generator function result".to_string());
+        self.return_types.push(Ty::Any);
+        self.yield_types.push(element);
+        self.in_scope(bindings, |inferer| inferer.infer_block(body));
+        self.yield_types.pop();
+        self.return_types.pop();
+    }
+    
+    /// Infers each arm with its pattern's bindings in scope, returning the type
+    /// each body produced.
+    fn infer_arms<B, T>(&mut self, subject: &Ty, arms: &[MatchArm<B>], mut infer_body: impl FnMut(&mut Self, &B) -> T) -> Vec<T> {
+        arms.iter()
+            .map(|arm| {
+                let mut bindings = Vec::new();
+                self.infer_pattern(&arm.pattern, subject, &mut bindings);
+                self.in_scope(bindings, |inferer| {
+                    if let Some(guard) = &arm.guard {
+                        inferer.infer_expr(guard);
+                    }
+                    infer_body(inferer, &arm.body)
+                })
+            })
+            .collect()
+    }
+    
+    fn infer_pattern(&mut self, pattern: &Pattern, ty: &Ty, bindings: &mut Vec<(String, Ty)>) {
+        match pattern {
+            Pattern::Wildcard => {}
+            Pattern::Binding(name) => bindings.push((name.clone(), ty.clone())),
+            Pattern::Literal(literal) => {
+                let literal = self.infer_expr(literal);
+                self.expect(ty, &literal, || "match pattern".to_string());
+            }
+            Pattern::Variant { enum_name, variant, fields } => {
+                self.expect(ty, &Ty::Named(enum_name.clone()), || "match pattern".to_string());
+                let payload = self.variant_types(enum_name, variant, fields.len());
+                for (field, field_ty) in fields.iter().zip(&payload) {
+                    self.infer_pattern(field, field_ty, bindings);
+                }
+            }
+            Pattern::Tuple(elements) => {
+                let element_types: Vec<Ty> = elements.iter().map(|_| self.fresh()).collect();
+                self.expect(ty, &Ty::Tuple(element_types.clone()), || "match pattern".to_string());
+                for (element, element_ty) in elements.iter().zip(&element_types) {
+                    self.infer_pattern(element, element_ty, bindings);
+                }
+            }
+        }
+    }
+    
+    /// Binds the names of a `let` or assignment pattern to the parts of `value`.
+    fn destructure(&mut self, pattern: &Pattern, value: &Ty, bindings: &mut Vec<(String, Ty)>) {
+        let shape = self.pattern_shape(pattern);
+        self.expect(&shape, value, || "destructured value".to_string());
+        self.infer_pattern(pattern, &shape, bindings);
+    }
+    
+    /// The type a value must have to be destructured by `pattern`, with a
+    /// fresh variable for each name or `_`.
+    fn pattern_shape(&mut self, pattern: &Pattern) -> Ty {
+        match pattern {
+            Pattern::Tuple(elements) => Ty::Tuple(elements.iter().map(|element| self.pattern_shape(element)).collect()),
+            _ => self.fresh(),
+        }
+    }
+    
+    // --- expressions ---
+    
+    fn infer_expr(&mut self, expr: &Expr) -> Ty {
+        match expr {
+            Expr::Integer(_) => Ty::Int,
+            Expr::Float(_) => Ty::Float,
+            Expr::String(_) => Ty::Str,
+            Expr::Interpolation(pieces) => {
+                for piece in pieces {
+                    self.infer_expr(piece);
+                }
+                Ty::Str
+            }
+            Expr::Boolean(_) => Ty::Bool,
+            Expr::Nil => Ty::Nil,
+            Expr::Identifier(name) => match self.lookup(name) {
+                Some(scheme) => self.instantiate(&scheme),
+                // defined somewhere inference cannot see, such as another module
+                None => self.fresh(),
+            },
+            Expr::List(elements) => {
+                let mut element = None;
+                for value in elements {
+                    let value = self.infer_expr(value);
+                    element = Some(self.join_into(element, &value));
+                }
+                Ty::List(Box::new(element.unwrap_or_else(|| self.fresh())))
+            }
+            Expr::Map(entries) => {
+                let (mut key, mut value) = (None, None);
+                for (entry_key, entry_value) in entries {
+                    let entry_key = self.infer_expr(entry_key);
+                    key = Some(self.join_into(key, &entry_key));
+                    let entry_value = self.infer_expr(entry_value);
+                    value = Some(self.join_into(value, &entry_value));
+                }
+                let key = key.unwrap_or_else(|| self.fresh());
+                let value = value.unwrap_or_else(|| self.fresh());
+                map(&key, &value)
+            }
+            Expr::Tuple(elements) => Ty::Tuple(elements.iter().map(|element| self.infer_expr(element)).collect()),
+            Expr::Index { object, index: index_expr, .. } => {
+                let object = self.infer_expr(object);
+                let index = self.infer_expr(index_expr);
+                // a tuple's values differ in type, so only a literal index says which one is read
+                if let (Ty::Tuple(elements), Expr::Integer(n)) = (self.resolve(&object), index_expr.as_ref()) {
+                    if let Some(element) = usize::try_from(*n).ok().and_then(|n| elements.get(n)) {
+                        return element.clone();
+                    }
+                }
+                self.index_type(&object, &index)
+            }
+            Expr::Get { object, name, .. } => {
+                let object = self.infer_expr(object);
+                self.member_type(&object, name)
+            }
+            Expr::StructInit { name, fields } => {
+                for (field, value) in fields {
+                    let value = self.infer_expr(value);
+                    let field_ty = self.field_type(name, field);
+                    self.expect(&field_ty, &value, || format!("field '{}' of '{}'", field, name));
+                }
+                Ty::Named(name.clone())
+            }
+            Expr::EnumVariant { enum_name, variant, arguments } => {
+                let payload = self.variant_types(enum_name, variant, arguments.len());
+                for (i, (argument, expected)) in arguments.iter().zip(&payload).enumerate() {
+                    let argument = self.infer_expr(argument);
+                    self.expect(expected, &argument, || format!("value {} of '{}::{}'", i + 1, enum_name, variant));
+                }
+                Ty::Named(enum_name.clone())
+            }
+            Expr::Conditional { condition, then_branch, else_branch } => {
+                self.infer_expr(condition);
+                let then_ty = self.infer_expr(then_branch);
+                let Some(else_branch) = else_branch else {
+                    return then_ty;
+                };
+                let else_ty = self.infer_expr(else_branch);
+                self.join(&then_ty, &else_ty).unwrap_or_else(|| {
+                    self.expect(&then_ty, &else_ty, || "else branch".to_string());
+                    then_ty
+                })
+            }
+            Expr::Match { subject, arms } => {
+                let subject = self.infer_expr(subject);
+                let arm_types = self.infer_arms(&subject, arms, |inferer, body| inferer.infer_expr(body));
+                let mut result: Option<Ty> = None;
+                for arm in arm_types {
+                    result = Some(match result {
+                        None => arm,
+                        Some(result) => self.join(&result, &arm).unwrap_or_else(|| {
+                            self.expect(&result, &arm, || "match arm".to_string());
+                            result
+                        }),
+                    });
+                }
+                result.unwrap_or_else(|| self.fresh())
+            }
+            Expr::Block { statements, value } => self.in_scope(Vec::new(), |inferer| {
+                inferer.infer_block(statements);
+                match value {
+                    Some(value) => inferer.infer_expr(value),
+                    None => Ty::Nil,
+                }
+            }),
+            Expr::Binary { left, operator, right, line } => {
+                let left = self.infer_expr(left);
+                let right = self.infer_expr(right);
+                self.line = *line;
+                self.binary_type(&left, operator, &right)
+            }
+            Expr::Unary { operator, operand, line } => {
+                let operand = self.infer_expr(operand);
+                self.line = *line;
+                match (operator, self.resolve(&operand)) {
+                    (UnaryOp::Not, _) => Ty::Bool,
+                    (UnaryOp::Minus, Ty::Int | Ty::Float | Ty::Num | Ty::Var(_) | Ty::Any) => operand,
+                    (UnaryOp::Minus, other) => {
+                        let message = format!("Cannot apply '-' to {}", self.display(&other));
+                        self.error(message);
+                        self.fresh()
+                    }
+                }
+            }
+            Expr::Range { start, end, step, .. } => {
+                for bound in [Some(start), Some(end), step.as_ref()].into_iter().flatten() {
+                    let bound = self.infer_expr(bound);
+                    self.expect(&Ty::Int, &bound, || "range bound".to_string());
+                }
+                Ty::Range
+            }
+            Expr::Logical { left, operator, right } => {
+                let left = self.infer_expr(left);
+                let right = self.infer_expr(right);
+                match operator {
+                    LogicalOp::And | LogicalOp::Or => Ty::Bool,
+                    LogicalOp::Coalesce => {
+                        self.expect(&left, &right, || "'??' fallback".to_string());
+                        left
+                    }
+                }
+            }
+            Expr::Call { callee, arguments, line } => {
+                let callee_ty = self.infer_expr(callee);
+                let arguments: Vec<Ty> = arguments.iter().map(|argument| self.infer_expr(argument)).collect();
+                self.line = *line;
+                self.call_type(callee, &callee_ty, arguments)
+            }
+            Expr::Lambda { params, param_types, return_type, body, line } => {
+                self.line = *line;
+                let param_types: Vec<Ty> = param_types.iter().map(|ty| self.annotation(ty)).collect();
+                let returns = self.annotation(return_type);
+                self.infer_function(params, param_types.clone(), returns.clone(), body);
+                Ty::Fn(param_types, Box::new(returns))
+            }
+        }
+    }
+    
+    fn call_type(&mut self, callee: &Expr, callee_ty: &Ty, arguments: Vec<Ty>) -> Ty {
+        let name = match callee {
+            Expr::Identifier(name) => format!("'{}'", name),
+            _ => "function".to_string(),
+        };
+        match self.resolve(callee_ty) {
+            Ty::Fn(params, returns) => {
+                if params.len() != arguments.len() {
+                    self.error(format!(
+                        "Function {} expected {} arguments but got {}",
+                        name,
+                        params.len(),
+                        arguments.len()
+                    ));
+                }
+                for (i, (param, argument)) in params.iter().zip(&arguments).enumerate() {
+                    self.expect(param, argument, || format!("argument {} of {}", i + 1, name));
+                }
+                *returns
+            }
+            Ty::Var(_) => {
+                let returns = self.fresh();
+                let ty = Ty::Fn(arguments, Box::new(returns.clone()));
+                self.expect(callee_ty, &ty, || name);
+                returns
+            }
+            Ty::Nil => self.fresh(),
+            Ty::Any => Ty::Any,
+            other => {
+                let message = format!("Cannot call {}", self.display(&other));
+                self.error(message);
+                self.fresh()
+            }
+        }
+    }
+    
+    fn index_type(&mut self, object: &Ty, index: &Ty) -> Ty {
+        match self.resolve(object) {
+            Ty::List(element) => {
+                self.expect(&Ty::Int, index, || "list index".to_string());
+                *element
+            }
+            Ty::Str => {
+                self.expect(&Ty::Int, index, || "string index".to_string());
+                Ty::Str
+            }
+            Ty::Map(key, value) => {
+                self.expect(&key, index, || "map key".to_string());
+                *value
+            }
+            Ty::Tuple(_) => {
+                self.expect(&Ty::Int, index, || "tuple index".to_string());
+                self.fresh()
+            }
+            // could be a list, a map or a string
+            Ty::Var(_) | Ty::Nil => self.fresh(),
+            Ty::Any => Ty::Any,
+            other => {
+                let message = format!("Cannot index into {}", self.display(&other));
+                self.error(message);
+                self.fresh()
+            }
+        }
+    }
+    
+    fn member_type(&mut self, object: &Ty, name: &str) -> Ty {
+        match self.resolve(object) {
+            Ty::Named(def) => self.field_type(&def, name),
+            Ty::Map(key, value) => {
+                self.expect(&key, &Ty::Str, || format!("key of map read as '.{}'", name));
+                *value
+            }
+            // a module or a value whose type is not known yet
+            _ => self.fresh(),
+        }
+    }
+    
+    /// The type of `left op right`, following the rules of `Interpreter::apply_binary_op`.
+    fn binary_type(&mut self, left: &Ty, op: &BinaryOp, right: &Ty) -> Ty {
+        let (l, r) = (self.resolve(left), self.resolve(right));
+        let result = match op {
+            BinaryOp::Equal | BinaryOp::NotEqual => {
+                if (l.is_numeric() && r.is_numeric()) || self.unify(&l, &r).is_ok() {
+                    Some(Ty::Bool)
+                } else {
+                    None
+                }
+            }
+            BinaryOp::Less | BinaryOp::Greater | BinaryOp::LessEqual | BinaryOp::GreaterEqual => {
+                self.numeric_operands(&l, &r).map(|_| Ty::Bool)
+            }
+            BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor | BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+                (self.unify(&l, &Ty::Int).is_ok() && self.unify(&r, &Ty::Int).is_ok()).then_some(Ty::Int)
+            }
+            // `+` also concatenates a string with a string or a number
+            BinaryOp::Add if l == Ty::Str || r == Ty::Str => {
+                let other = if l == Ty::Str { &r } else { &l };
+                (other.is_numeric() || matches!(other, Ty::Str | Ty::Var(_) | Ty::Any)).then_some(Ty::Str)
+            }
+            _ => self.numeric_operands(&l, &r),
+        };
+        
+        result.unwrap_or_else(|| {
+            let message = format!(
+                "Cannot apply '{}' to {} and {}",
+                op.symbol(),
+                self.display(&l),
+                self.display(&r)
+            );
+            self.error(message);
+            self.fresh()
+        })
+    }
+    
+    /// The type of arithmetic on `l` and `r`: a float if either is one, an int
+    /// if both are, a `num` if either might be either, or `None` if either is
+    /// not a number.
+    fn numeric_operands(&mut self, l: &Ty, r: &Ty) -> Option<Ty> {
+        match (l, r) {
+            (Ty::Int, Ty::Int) => Some(Ty::Int),
+            (Ty::Any, other) | (other, Ty::Any) if other.is_numeric() || matches!(other, Ty::Var(_) | Ty::Any) => Some(Ty::Any),
+            (Ty::Float, other) | (other, Ty::Float) if other.is_numeric() => Some(Ty::Float),
+            (Ty::Num, other) | (other, Ty::Num) if other.is_numeric() => Some(Ty::Num),
+            (Ty::Var(_), Ty::Var(_)) => self.unify(l, r).ok().map(|_| l.clone()),
+            // all that is known of the unknown side is that it is a number
+            (Ty::Var(_), number) | (number, Ty::Var(_)) if number.is_numeric() => {
+                let unknown = if matches!(l, Ty::Var(_)) { l } else { r };
+                let result = if *number == Ty::Float { Ty::Float } else { Ty::Num };
+                self.unify(unknown, &Ty::Num).ok().map(|_| result)
+            }
+            _ => None,
+        }
+    }
+    
+    // --- display ---
+    
+    /// Writes `ty` the way annotations do, naming unknown parts `'a`, `'b`, ...
+    fn display(&self, ty: &Ty) -> String {
+        let mut names = HashMap::new();
+        self.display_with(&self.zonk(ty), &mut names)
+    }
+    
+    fn display_with(&self, ty: &Ty, names: &mut HashMap<usize, String>) -> String {
+        match ty {
+            Ty::Var(var) if self.callables.contains(var) => "fn".to_string(),
+            Ty::Var(var) => {
+                let next = names.len();
+                names
+                    .entry(*var)
+                    .or_insert_with(|| match u8::try_from(next).ok().filter(|n| *n < 26) {
+                        Some(n) => format!("'{}", (b'a' + n) as char),
+                        None => format!("'t{}", next),
+                    })
+                    .clone()
+            }
+            Ty::Int => "int".to_string(),
+            Ty::Float => "float".to_string(),
+            Ty::Num => "num".to_string(),
+            Ty::Str => "str".to_string(),
+            Ty::Bool => "bool".to_string(),
+            Ty::Nil => "nil".to_string(),
+            Ty::Range => "range".to_string(),
+            Ty::List(element) => format!("list<{}>", self.display_with(element, names)),
+            Ty::Generator(element) => format!("generator<{}>", self.display_with(element, names)),
+            Ty::Map(key, value) => format!(
+                "map<{}, {}>",
+                self.display_with(key, names),
+                self.display_with(value, names)
+            ),
+            Ty::Fn(params, returns) => {
+                let params: Vec<String> = params.iter().map(|param| self.display_with(param, names)).collect();
+                format!("fn({}) -> {}", params.join(", "), self.display_with(returns, names))
+            }
+            Ty::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|element| self.display_with(element, names)).collect();
+                match elements.as_slice() {
+                    [single] => format!("({},)", single),
+                    _ => format!("({})", elements.join(", ")),
+                }
+            }
+            Ty::Named(name) => name.clone(),
+            Ty::Any => "any".to_string(),
+        }
+    }
+}
+
+fn map(key: &Ty, value: &Ty) -> Ty {
+    Ty::Map(Box::new(key.clone()), Box::new(value.clone()))
+}
+
+/// `ty` with the variables in `replacements` replaced.
+fn substitute(ty: &Ty, replacements: &HashMap<usize, Ty>) -> Ty {
+    match ty {
+        Ty::Var(var) => replacements.get(var).cloned().unwrap_or_else(|| ty.clone()),
+        Ty::List(element) => Ty::List(Box::new(substitute(element, replacements))),
+        Ty::Generator(element) => Ty::Generator(Box::new(substitute(element, replacements))),
+        Ty::Map(key, value) => Ty::Map(
+            Box::new(substitute(key, replacements)),
+            Box::new(substitute(value, replacements)),
+        ),
+        Ty::Fn(params, returns) => Ty::Fn(
+            params.iter().map(|param| substitute(param, replacements)).collect(),
+            Box::new(substitute(returns, replacements)),
+        ),
+        Ty::Tuple(elements) => Ty::Tuple(elements.iter().map(|element| substitute(element, replacements)).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    
+    fn infer_source(source: &str) -> Inference {
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        infer(&ast)
+    }
+    
+    #[test]
+    fn test_infer_bindings() {
+        let source = "
+            let xs = [1, 2, 3];
+            let ages = {\"ann\": 31};
+            fn id(x) { return x; }
+            let name = id(\"ferris\");
+            let count = id(len(xs));
+            fn fact(n) { if (n <= 1) { return 1; } return n * fact(n - 1); }
+            let half = |x| x / 2.0;
+            let mixed = [1, \"one\"];
+            let ratio: float = 1.0;
+            let scores = [1, 2.5];
+            let first = scores[0] / 2;
+            let missing: int = nil;
+        ";
+        let inference = infer_source(source);
+        
+        assert!(inference.errors.is_empty());
+        assert_eq!(inference.bindings, vec![
+            "line 2: xs: list<int>",
+            "line 3: ages: map<str, int>",
+            "line 4: id: fn('a) -> 'a",
+            "line 5: name: str",
+            "line 6: count: int",
+            "line 7: fact: fn(num) -> num",
+            "line 8: half: fn(num) -> float",
+            "line 9: mixed: list<any>",
+            "line 10: ratio: float",
+            "line 11: scores: list<num>",
+            "line 12: first: num",
+            "line 13: missing: int",
+        ]);
+    }
+    
+    #[test]
+    fn test_infer_contradictions() {
+        let source = "
+            let total;
+            let ready = true;
+            if (ready) {
+                total = \"none\";
+            }
+            print(total * 2);
+            let count = 0;
+            count = \"many\";
+            fn greet(name) { return \"Hello, \" + name; }
+            greet(1, 2);
+            let whole: int = 1.5;
+            count = 2.5;
+            let ratio: float = 1;
+            fn avg(a, b) { return (a + b) / 2; }
+            let mean = avg(1.5, 2.5);
+        ";
+        
+        assert_eq!(infer_source(source).errors, vec![
+            "Cannot apply '*' to str and int at line 7",
+            "Type mismatch for variable 'count': expected int, found str at line 9",
+            "Function 'greet' expected 1 arguments but got 2 at line 11",
+            "Type mismatch for variable 'whole': expected int, found float at line 12",
+            "Type mismatch for variable 'count': expected int, found float at line 13",
+            "Type mismatch for variable 'ratio': expected float, found int at line 14",
+        ]);
+    }
+    
+    #[test]
+    fn test_infer_generic_lets() {
+        let source = "
+            let id = |a| a;
+            let n = id(1);
+            let s = id(\"s\");
+            fn pair(x, y) { return (x, y); }
+            let xs = [];
+            push(xs, 1);
+            push(xs, \"one\");
+        ";
+        let inference = infer_source(source);
+        
+        // each unknown type has its own name across the whole listing
+        assert_eq!(inference.bindings, vec![
+            "line 2: id: fn('a) -> 'a",
+            "line 3: n: int",
+            "line 4: s: str",
+            "line 5: pair: fn('b, 'c) -> ('b, 'c)",
+            "line 6: xs: list<int>",
+        ]);
+        // a list is a single value, so its element type is not generic
+        assert_eq!(inference.errors, vec![
+            "Type mismatch for argument 2 of 'push': expected int, found str at line 8",
+        ]);
+    }
+    
+    #[test]
+    fn test_infer_annotations() {
+        let source = "
+            fn measure(a: int) -> num { return \"long\"; }
+            fn pick(whole) -> num { if (whole) { return 1; } return 2.5; }
+            let scaled = pick(true) * 2;
+            fn keep(callback: fn) { return callback; }
+            keep(5);
+            let anything: any = 1;
+            anything = \"one\";
+        ";
+        let inference = infer_source(source);
+        
+        assert!(inference.bindings.contains(&"line 3: pick: fn('a) -> num".to_string()));
+        assert!(inference.bindings.contains(&"line 4: scaled: num".to_string()));
+        assert!(inference.bindings.contains(&"line 5: keep: fn(fn) -> fn".to_string()));
+        assert_eq!(inference.errors, vec![
+            "Type mismatch for return value: expected num, found str at line 2",
+            "Type mismatch for argument 1 of 'keep': expected fn, found int at line 6",
+        ]);
+    }
+    
+    #[test]
+    fn test_infer_tuples() {
+        let source = "
+            fn swap(pair) { let (a, b) = pair; return (b, a); }
+            let (name, age) = swap((30, \"Ferris\"));
+            let single = (1.5,);
+            let first = single[0];
+            let (x, y) = (1, 2, 3);
+        ";
+        let inference = infer_source(source);
+        
+        assert!(inference.bindings.contains(&"line 2: swap: fn(('a, 'b)) -> ('b, 'a)".to_string()));
+        assert!(inference.bindings.contains(&"line 3: name: str".to_string()));
+        assert!(inference.bindings.contains(&"line 3: age: int".to_string()));
+        assert!(inference.bindings.contains(&"line 4: single: (float,)".to_string()));
+        assert!(inference.bindings.contains(&"line 5: first: float".to_string()));
+        assert_eq!(inference.errors, vec![
+            "Type mismatch for destructured value: expected ('a, 'b), found (int, int, int) at line 6",
+        ]);
+    }
+}
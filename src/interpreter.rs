@@ -1,223 +1,2335 @@
-use std::collections::HashMap;
-use crate::ast::{Expr, Stmt, BinaryOp, UnaryOp};
-use crate::value::Value;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use crate::ast::{Expr, Stmt, BinaryOp, LogicalOp, MatchArm, Pattern, UnaryOp};
+use crate::builtins::define_builtins;
+use crate::environment::Environment;
+use crate::generator::{body_statements, Exit, Frame, Generator, Step, TryStage};
+use crate::lexer::Lexer;
+use crate::modules::{self, ModuleLoader};
+use crate::parser::Parser;
+use crate::typecheck;
+use crate::value::{EnumDef, EnumValue, Function, MapKey, Module, NativeFn, Range, StructDef, StructInstance, Value};
+
+// deep enough for recursive scripts, shallow enough not to overflow a stack of STACK_SIZE
+const MAX_CALL_DEPTH: usize = 5_000;
+
+/// The Rust stack a program needs to reach `MAX_CALL_DEPTH`, so programs run on
+/// a thread of this size. In a debug build a script call costs about 13 KiB
+/// when it sits directly in a function body and about 43 KiB from inside a
+/// `for`, `while`, `try` and `match`; release builds need a quarter of that.
+/// 64 KiB per call covers both, and the memory is only committed as it is used.
+pub const STACK_SIZE: usize = MAX_CALL_DEPTH * 64 * 1024;
+
+/// How a statement finished. Anything other than `Normal` unwinds enclosing
+/// statements until a loop or function call handles it.
+pub enum ControlFlow {
+    Normal,
+    Return(Value),
+    Break(Option<String>),
+    Continue(Option<String>),
+}
+
+impl ControlFlow {
+    /// Whether a `break`/`continue` with this `target` applies to a loop labelled `label`.
+    fn targets_loop(target: &Option<String>, label: &Option<String>) -> bool {
+        target.is_none() || target == label
+    }
+}
+
+/// Why evaluation failed. It unwinds to the nearest `try` with a `catch`, or
+/// out of the program if there is none. The failure is boxed so that the
+/// `Result` returned by every evaluation step stays no larger than a `Value`.
+pub struct RuntimeError(Box<Failure>);
+
+enum Failure {
+    /// A failure detected by the interpreter, such as division by zero.
+    Error { kind: ErrorKind, message: String },
+    /// A value raised with `throw`.
+    Thrown(Value),
+}
+
+impl RuntimeError {
+    fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        RuntimeError(Box::new(Failure::Error { kind, message: message.into() }))
+    }
+    
+    fn thrown(value: Value) -> Self {
+        RuntimeError(Box::new(Failure::Thrown(value)))
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &*self.0 {
+            Failure::Error { message, .. } => write!(f, "{}", message),
+            Failure::Thrown(value) => write!(f, "Uncaught exception: {}", value.to_nested_string()),
+        }
+    }
+}
+
+// errors from value and builtin helpers carry only a message
+impl From<String> for RuntimeError {
+    fn from(message: String) -> Self {
+        RuntimeError::new(ErrorKind::Other, message)
+    }
+}
+
+/// The category of an interpreter error, visible to scripts as `error.kind`.
+#[derive(Debug, Clone, Copy)]
+enum ErrorKind {
+    Type,
+    UndefinedVariable,
+    DivisionByZero,
+    Overflow,
+    IndexOutOfBounds,
+    KeyNotFound,
+    StackOverflow,
+    Constant,
+    Other,
+}
+
+impl ErrorKind {
+    fn name(self) -> &'static str {
+        match self {
+            ErrorKind::Type => "TypeError",
+            ErrorKind::UndefinedVariable => "UndefinedVariable",
+            ErrorKind::DivisionByZero => "DivisionByZero",
+            ErrorKind::Overflow => "Overflow",
+            ErrorKind::IndexOutOfBounds => "IndexOutOfBounds",
+            ErrorKind::KeyNotFound => "KeyNotFound",
+            ErrorKind::StackOverflow => "StackOverflow",
+            ErrorKind::Constant => "ConstantAssignment",
+            ErrorKind::Other => "RuntimeError",
+        }
+    }
+}
 
 pub struct Interpreter {
-    globals: HashMap<String, Value>,
+    // innermost scope of the code currently executing
+    environment: Rc<RefCell<Environment>>,
+    call_depth: usize,
+    modules: ModuleLoader,
+    // directory of the module currently running, where its imports are looked up first
+    module_dir: PathBuf,
+    // the built-in `Error` struct that caught interpreter errors are turned into
+    error_def: Rc<StructDef>,
 }
 
 impl Interpreter {
+    #[cfg(test)]
     pub fn new() -> Self {
+        Self::with_modules(None, Vec::new())
+    }
+    
+    /// An interpreter for the program in `script` (if it was read from a file)
+    /// that also looks for imported modules in `search_path`.
+    pub fn with_modules(script: Option<&Path>, search_path: Vec<PathBuf>) -> Self {
+        let mut modules = ModuleLoader::new(search_path);
+        if let Some(script) = script {
+            modules.set_entry_script(script);
+        }
+        let error_def = Rc::new(StructDef {
+            name: "Error".to_string(),
+            fields: vec!["kind".to_string(), "message".to_string()],
+        });
         Self {
-            globals: HashMap::new(),
+            environment: Rc::new(RefCell::new(Self::globals(&error_def))),
+            call_depth: 0,
+            modules,
+            module_dir: script.and_then(Path::parent).map(Path::to_path_buf).unwrap_or_default(),
+            error_def,
         }
     }
     
+    /// A global scope holding only the built-in functions and the `Error` struct.
+    fn globals(error_def: &Rc<StructDef>) -> Environment {
+        let mut globals = Environment::new();
+        define_builtins(&mut globals);
+        globals.define(error_def.name.clone(), Value::StructDef(Rc::clone(error_def)));
+        globals
+    }
+    
     pub fn interpret(&mut self, statements: Vec<Stmt>) -> Result<(), String> {
-        for stmt in statements {
-            self.execute_stmt(stmt)?;
+        for stmt in &statements {
+            self.execute_stmt(stmt).map_err(|error| error.to_string())?;
         }
         Ok(())
     }
     
-    fn execute_stmt(&mut self, stmt: Stmt) -> Result<(), String> {
+    fn execute_stmt(&mut self, stmt: &Stmt) -> Result<ControlFlow, RuntimeError> {
         match stmt {
             Stmt::Expression(expr) => {
                 self.evaluate_expr(expr)?;
             }
-            Stmt::Let { name, value } => {
-                let val = self.evaluate_expr(value)?;
-                self.globals.insert(name, val);
-            }
-            Stmt::Assignment { name, value } => {
-                let val = self.evaluate_expr(value)?;
-                if self.globals.contains_key(&name) {
-                    self.globals.insert(name, val);
-                } else {
-                    return Err(format!("Undefined variable '{}'", name));
-                }
-            }
+            Stmt::Let { .. }
+            | Stmt::Assignment { .. }
+            | Stmt::Destructure { .. }
+            | Stmt::DestructuringAssignment { .. }
+            | Stmt::IndexAssignment { .. }
+            | Stmt::SetField { .. }
+            | Stmt::CompoundAssignment { .. } => self.execute_binding(stmt)?,
             Stmt::If { condition, then_stmt, else_stmt } => {
                 let condition_val = self.evaluate_expr(condition)?;
                 if self.is_truthy(&condition_val) {
-                    self.execute_stmt(*then_stmt)?;
+                    return self.execute_stmt(then_stmt);
                 } else if let Some(else_stmt) = else_stmt {
-                    self.execute_stmt(*else_stmt)?;
-                }
-            }
-            Stmt::While { condition, body } => {
-                loop {
-                    let condition_val = self.evaluate_expr(condition.clone())?;
-                    if !self.is_truthy(&condition_val) {
-                        break;
-                    }
-                    self.execute_stmt((*body).clone())?;
+                    return self.execute_stmt(else_stmt);
                 }
             }
+            Stmt::While { condition, body, label } => return self.execute_while(condition, body, label),
+            Stmt::For { var, iterable, body, label } => return self.execute_for(var, iterable, body, label),
+            Stmt::Break(label) => return Ok(ControlFlow::Break(label.clone())),
+            Stmt::Continue(label) => return Ok(ControlFlow::Continue(label.clone())),
             Stmt::Block(statements) => {
-                for stmt in statements {
-                    self.execute_stmt(stmt)?;
-                }
+                let scope = Environment::with_enclosing(Rc::clone(&self.environment));
+                return self.execute_block(statements, Rc::new(RefCell::new(scope)));
             }
             Stmt::Print(expr) => {
                 let val = self.evaluate_expr(expr)?;
                 println!("{}", val);
             }
+            Stmt::Function { name, params, body, .. } => {
+                let function = self.function(Some(name), params, body);
+                self.define(name.clone(), function);
+            }
+            Stmt::Struct { .. } | Stmt::Enum { .. } => self.execute_type_declaration(stmt),
+            Stmt::Match { subject, arms } => {
+                let subject_val = self.evaluate_expr(subject)?;
+                let (body, scope) = self.select_arm(&subject_val, arms)?;
+                return self.execute_block(std::slice::from_ref(body), scope);
+            }
+            Stmt::Import { path, name, .. } => {
+                let module = self.import(path)?;
+                self.define(name.clone(), module);
+            }
+            Stmt::Throw(value) => {
+                let value = self.evaluate_expr(value)?;
+                return Err(RuntimeError::thrown(value));
+            }
+            Stmt::Try { body, catch, finally } => return self.execute_try(body, catch, finally),
+            Stmt::Return { value, .. } => {
+                let value = match value {
+                    Some(expr) => self.evaluate_expr(expr)?,
+                    None => Value::Nil,
+                };
+                return Ok(ControlFlow::Return(value));
+            }
+            Stmt::Yield { .. } => unreachable!("generator bodies are run by `resume`"),
+        }
+        Ok(ControlFlow::Normal)
+    }
+    
+    // The statements and expressions below have a method of their own rather
+    // than an arm of `execute_stmt` or `evaluate_expr`. Every call to a Ferris
+    // function passes through those two, several times over, so whatever stack
+    // their arms need is paid at every level of recursion.
+    
+    /// Runs a `let` or any of the assignment forms.
+    fn execute_binding(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
+        match stmt {
+            Stmt::Let { name, value, constant, .. } => {
+                let val = self.evaluate_expr(value)?;
+                if *constant {
+                    self.environment.borrow_mut().define_constant(name.clone(), val);
+                } else {
+                    self.define(name.clone(), val);
+                }
+            }
+            Stmt::Assignment { name, value, .. } => {
+                let val = self.evaluate_expr(value)?;
+                self.assign(name, val)?;
+            }
+            Stmt::Destructure { pattern, value, constant, .. } => {
+                let val = self.evaluate_expr(value)?;
+                let mut bindings = Vec::new();
+                self.destructure(pattern, val, &mut bindings)?;
+                for (name, val) in bindings {
+                    if *constant {
+                        self.environment.borrow_mut().define_constant(name, val);
+                    } else {
+                        self.define(name, val);
+                    }
+                }
+            }
+            Stmt::DestructuringAssignment { pattern, value, .. } => {
+                let val = self.evaluate_expr(value)?;
+                let mut bindings = Vec::new();
+                self.destructure(pattern, val, &mut bindings)?;
+                for (name, val) in bindings {
+                    self.assign(&name, val)?;
+                }
+            }
+            Stmt::IndexAssignment { object, index, value } => {
+                let object_val = self.evaluate_expr(object)?;
+                let index_val = self.evaluate_expr(index)?;
+                let val = self.evaluate_expr(value)?;
+                self.set_index(object_val, &index_val, val)?;
+            }
+            Stmt::SetField { object, name, value } => {
+                let object_val = self.evaluate_expr(object)?;
+                let val = self.evaluate_expr(value)?;
+                self.set_member(object_val, name, val)?;
+            }
+            Stmt::CompoundAssignment { target, operator, value, .. } => self.execute_compound_assignment(target, operator, value)?,
+            _ => unreachable!("only let and assignment statements are passed in"),
         }
         Ok(())
     }
     
-    fn evaluate_expr(&self, expr: Expr) -> Result<Value, String> {
-        match expr {
-            Expr::Number(n) => Ok(Value::Number(n)),
-            Expr::String(s) => Ok(Value::String(s)),
+    fn execute_compound_assignment(&mut self, target: &Expr, operator: &BinaryOp, value: &Expr) -> Result<(), RuntimeError> {
+        match target {
             Expr::Identifier(name) => {
-                self.globals.get(&name)
-                    .cloned()
-                    .ok_or_else(|| format!("Undefined variable '{}'", name))
+                let current = self.lookup(name)?;
+                let rhs = self.evaluate_expr(value)?;
+                let result = self.apply_binary_op(current, operator, rhs)?;
+                self.assign(name, result)
             }
-            Expr::Binary { left, operator, right } => {
-                let left_val = self.evaluate_expr(*left)?;
-                let right_val = self.evaluate_expr(*right)?;
-                self.apply_binary_op(left_val, operator, right_val)
+            Expr::Index { object, index, .. } => {
+                let object_val = self.evaluate_expr(object)?;
+                let index_val = self.evaluate_expr(index)?;
+                let current = self.get_index(object_val.clone(), &index_val)?;
+                let rhs = self.evaluate_expr(value)?;
+                let result = self.apply_binary_op(current, operator, rhs)?;
+                self.set_index(object_val, &index_val, result)
             }
-            Expr::Unary { operator, operand } => {
-                let operand_val = self.evaluate_expr(*operand)?;
-                self.apply_unary_op(operator, operand_val)
+            Expr::Get { object, name, .. } => {
+                let object_val = self.evaluate_expr(object)?;
+                let current = self.get_member(object_val.clone(), name, false)?;
+                let rhs = self.evaluate_expr(value)?;
+                let result = self.apply_binary_op(current, operator, rhs)?;
+                self.set_member(object_val, name, result)
             }
+            _ => unreachable!("the parser only produces identifier, index and field targets"),
         }
     }
     
-    fn apply_binary_op(&self, left: Value, op: BinaryOp, right: Value) -> Result<Value, String> {
-        match (left, right) {
-            (Value::Number(l), Value::Number(r)) => {
-                match op {
-                    BinaryOp::Add => Ok(Value::Number(l + r)),
-                    BinaryOp::Subtract => Ok(Value::Number(l - r)),
-                    BinaryOp::Multiply => Ok(Value::Number(l * r)),
-                    BinaryOp::Divide => {
-                        if r == 0.0 {
-                            Err("Division by zero".to_string())
-                        } else {
-                            Ok(Value::Number(l / r))
-                        }
-                    }
-                    BinaryOp::Equal => Ok(Value::Boolean((l - r).abs() < f64::EPSILON)),
-                    BinaryOp::NotEqual => Ok(Value::Boolean((l - r).abs() >= f64::EPSILON)),
-                    BinaryOp::Less => Ok(Value::Boolean(l < r)),
-                    BinaryOp::Greater => Ok(Value::Boolean(l > r)),
-                    BinaryOp::LessEqual => Ok(Value::Boolean(l <= r)),
-                    BinaryOp::GreaterEqual => Ok(Value::Boolean(l >= r)),
-                }
+    fn execute_while(&mut self, condition: &Expr, body: &Stmt, label: &Option<String>) -> Result<ControlFlow, RuntimeError> {
+        loop {
+            let condition_val = self.evaluate_expr(condition)?;
+            if !self.is_truthy(&condition_val) {
+                break;
             }
-            (Value::String(l), Value::String(r)) => {
-                match op {
-                    BinaryOp::Add => Ok(Value::String(format!("{}{}", l, r))), // String concatenation
-                    BinaryOp::Equal => Ok(Value::Boolean(l == r)),
-                    BinaryOp::NotEqual => Ok(Value::Boolean(l != r)),
-                    _ => Err("Invalid operation for strings".to_string()),
+            match self.execute_stmt(body)? {
+                ControlFlow::Normal => {}
+                ControlFlow::Break(target) if ControlFlow::targets_loop(&target, label) => break,
+                ControlFlow::Continue(target) if ControlFlow::targets_loop(&target, label) => continue,
+                flow => return Ok(flow),
+            }
+        }
+        Ok(ControlFlow::Normal)
+    }
+    
+    fn execute_for(&mut self, var: &str, iterable: &Expr, body: &Stmt, label: &Option<String>) -> Result<ControlFlow, RuntimeError> {
+        let iterable_val = self.evaluate_expr(iterable)?;
+        let mut iteration = Iteration::new(iterable_val)?;
+        while let Some(item) = iteration.next(self)? {
+            let mut scope = Environment::with_enclosing(Rc::clone(&self.environment));
+            scope.define(var.to_string(), item);
+            match self.execute_block(std::slice::from_ref(body), Rc::new(RefCell::new(scope)))? {
+                ControlFlow::Normal => {}
+                ControlFlow::Break(target) if ControlFlow::targets_loop(&target, label) => break,
+                ControlFlow::Continue(target) if ControlFlow::targets_loop(&target, label) => continue,
+                flow => return Ok(flow),
+            }
+        }
+        Ok(ControlFlow::Normal)
+    }
+    
+    fn execute_type_declaration(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Struct { name, fields, .. } => {
+                let def = StructDef {
+                    name: name.clone(),
+                    fields: fields.clone(),
+                };
+                self.define(name.clone(), Value::StructDef(Rc::new(def)));
+            }
+            Stmt::Enum { name, variants, .. } => {
+                let def = EnumDef {
+                    name: name.clone(),
+                    variants: variants.clone(),
+                };
+                self.define(name.clone(), Value::EnumDef(Rc::new(def)));
+            }
+            _ => unreachable!("only struct and enum declarations are passed in"),
+        }
+    }
+    
+    fn execute_try(
+        &mut self,
+        body: &[Stmt],
+        catch: &Option<(String, Vec<Stmt>)>,
+        finally: &Option<Vec<Stmt>>,
+    ) -> Result<ControlFlow, RuntimeError> {
+        let scope = Environment::with_enclosing(Rc::clone(&self.environment));
+        let mut result = self.execute_block(body, Rc::new(RefCell::new(scope)));
+        if let Some((name, handler)) = catch {
+            result = match result {
+                Err(error) => {
+                    let mut scope = Environment::with_enclosing(Rc::clone(&self.environment));
+                    scope.define(name.clone(), self.error_value(error));
+                    self.execute_block(handler, Rc::new(RefCell::new(scope)))
                 }
+                finished => finished,
+            };
+        }
+        if let Some(finally) = finally {
+            let scope = Environment::with_enclosing(Rc::clone(&self.environment));
+            // an error or jump out of `finally` replaces whatever the try was doing
+            match self.execute_block(finally, Rc::new(RefCell::new(scope)))? {
+                ControlFlow::Normal => {}
+                flow => return Ok(flow),
             }
-            // Handle string + number concatenation
-            (Value::String(s), Value::Number(n)) => {
-                match op {
-                    BinaryOp::Add => {
-                        // Convert number to string and concatenate
-                        let n_str = if n.fract() == 0.0 {
-                            format!("{}", n as i64) // Display as integer if no decimal part
-                        } else {
-                            format!("{}", n)
-                        };
-                        Ok(Value::String(format!("{}{}", s, n_str)))
-                    }
-                    _ => Err("Invalid operation for string and number".to_string()),
+        }
+        result
+    }
+    
+    /// A function value for a declaration or lambda, closing over the current scope.
+    fn function(&self, name: Option<&String>, params: &[String], body: &[Stmt]) -> Value {
+        Value::Function(Rc::new(Function {
+            name: name.cloned(),
+            params: params.to_vec(),
+            body: Rc::from(body),
+            generator: body.iter().any(Stmt::contains_yield),
+            closure: Rc::clone(&self.environment),
+        }))
+    }
+    
+    fn evaluate_expr(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        match expr {
+            Expr::Integer(n) => Ok(Value::Int(*n)),
+            Expr::Float(n) => Ok(Value::Float(*n)),
+            Expr::String(s) => Ok(Value::String(s.clone())),
+            Expr::Interpolation(pieces) => self.evaluate_interpolation(pieces),
+            Expr::Boolean(b) => Ok(Value::Boolean(*b)),
+            Expr::Nil => Ok(Value::Nil),
+            Expr::Identifier(name) => self.lookup(name),
+            Expr::List(elements) => Ok(Value::List(Rc::new(RefCell::new(self.evaluate_all(elements)?)))),
+            Expr::Tuple(elements) => Ok(Value::Tuple(Rc::from(self.evaluate_all(elements)?))),
+            Expr::Map(entries) => self.evaluate_map(entries),
+            Expr::Index { object, index, optional } => self.evaluate_index(object, index, *optional),
+            Expr::Get { object, name, optional } => {
+                let object_val = self.evaluate_expr(object)?;
+                self.get_member(object_val, name, *optional)
+            }
+            Expr::StructInit { name, fields } => self.evaluate_struct_init(name, fields),
+            Expr::Binary { left, operator, right, .. } => {
+                let left_val = self.evaluate_expr(left)?;
+                let right_val = self.evaluate_expr(right)?;
+                self.apply_binary_op(left_val, operator, right_val)
+            }
+            Expr::Range { start, end, step, inclusive } => self.evaluate_range(start, end, step.as_deref(), *inclusive),
+            Expr::Logical { left, operator, right } => self.evaluate_logical(left, operator, right),
+            Expr::Unary { operator, operand, .. } => {
+                let operand_val = self.evaluate_expr(operand)?;
+                self.apply_unary_op(operator, operand_val)
+            }
+            Expr::EnumVariant { enum_name, variant, arguments } => self.evaluate_enum_variant(enum_name, variant, arguments),
+            Expr::Conditional { condition, then_branch, else_branch } => {
+                let condition_val = self.evaluate_expr(condition)?;
+                if self.is_truthy(&condition_val) {
+                    self.evaluate_expr(then_branch)
+                } else if let Some(else_branch) = else_branch {
+                    self.evaluate_expr(else_branch)
+                } else {
+                    Ok(Value::Nil)
                 }
             }
-            // Handle number + string concatenation
-            (Value::Number(n), Value::String(s)) => {
-                match op {
-                    BinaryOp::Add => {
-                        // Convert number to string and concatenate
-                        let n_str = if n.fract() == 0.0 {
-                            format!("{}", n as i64) // Display as integer if no decimal part
-                        } else {
-                            format!("{}", n)
-                        };
-                        Ok(Value::String(format!("{}{}", n_str, s)))
-                    }
-                    _ => Err("Invalid operation for number and string".to_string()),
+            Expr::Match { subject, arms } => {
+                let subject_val = self.evaluate_expr(subject)?;
+                let (body, scope) = self.select_arm(&subject_val, arms)?;
+                self.evaluate_in_scope(body, scope)
+            }
+            Expr::Block { statements, value } => self.evaluate_block(statements, value.as_deref()),
+            Expr::Call { callee, arguments, .. } => self.evaluate_call(callee, arguments),
+            Expr::Lambda { params, body, .. } => Ok(self.function(None, params, body)),
+        }
+    }
+    
+    fn evaluate_interpolation(&mut self, pieces: &[Expr]) -> Result<Value, RuntimeError> {
+        let mut string = String::new();
+        for piece in pieces {
+            string += &self.evaluate_expr(piece)?.to_string();
+        }
+        Ok(Value::String(string))
+    }
+    
+    fn evaluate_all(&mut self, elements: &[Expr]) -> Result<Vec<Value>, RuntimeError> {
+        let mut values = Vec::with_capacity(elements.len());
+        for element in elements {
+            values.push(self.evaluate_expr(element)?);
+        }
+        Ok(values)
+    }
+    
+    fn evaluate_map(&mut self, entries: &[(Expr, Expr)]) -> Result<Value, RuntimeError> {
+        let mut map = BTreeMap::new();
+        for (key, value) in entries {
+            let key = MapKey::from_value(&self.evaluate_expr(key)?)?;
+            let value = self.evaluate_expr(value)?;
+            map.insert(key, value);
+        }
+        Ok(Value::Map(Rc::new(RefCell::new(map))))
+    }
+    
+    fn evaluate_index(&mut self, object: &Expr, index: &Expr, optional: bool) -> Result<Value, RuntimeError> {
+        let object_val = self.evaluate_expr(object)?;
+        if optional && matches!(object_val, Value::Nil) {
+            return Ok(Value::Nil);
+        }
+        let index_val = self.evaluate_expr(index)?;
+        if optional {
+            Ok(self.try_get_index(&object_val, &index_val)?.unwrap_or(Value::Nil))
+        } else {
+            self.get_index(object_val, &index_val)
+        }
+    }
+    
+    fn evaluate_struct_init(&mut self, name: &str, fields: &[(String, Expr)]) -> Result<Value, RuntimeError> {
+        let def = match self.lookup(name)? {
+            Value::StructDef(def) => def,
+            other => return Err(RuntimeError::new(ErrorKind::Type, format!("'{}' is not a struct", other))),
+        };
+        let mut values = vec![Value::Nil; def.fields.len()];
+        let mut initialized = vec![false; def.fields.len()];
+        for (field, value) in fields {
+            let index = def.fields
+                .iter()
+                .position(|f| f == field)
+                .ok_or_else(|| format!("Struct '{}' has no field '{}'", def.name, field))?;
+            values[index] = self.evaluate_expr(value)?;
+            initialized[index] = true;
+        }
+        if let Some(missing) = initialized.iter().position(|done| !done) {
+            return Err(format!("Missing field '{}' in '{}' initializer", def.fields[missing], def.name).into());
+        }
+        Ok(Value::Struct(Rc::new(RefCell::new(StructInstance { def, values }))))
+    }
+    
+    fn evaluate_range(&mut self, start: &Expr, end: &Expr, step: Option<&Expr>, inclusive: bool) -> Result<Value, RuntimeError> {
+        let start = self.evaluate_expr(start)?;
+        let end = self.evaluate_expr(end)?;
+        let step = match step {
+            Some(step) => self.evaluate_expr(step)?,
+            None => Value::Int(1),
+        };
+        match (start, end, step) {
+            (Value::Int(start), Value::Int(end), Value::Int(step)) => {
+                if step == 0 {
+                    return Err("Range step cannot be zero".to_string().into());
                 }
+                Ok(Value::Range(Range { start, end, step, inclusive }))
             }
-            _ => Err("Invalid operands for binary operation".to_string()),
+            _ => Err(RuntimeError::new(ErrorKind::Type, "Range bounds and step must be integers")),
         }
     }
     
-    fn apply_unary_op(&self, op: UnaryOp, operand: Value) -> Result<Value, String> {
-        match (op, operand) {
-            (UnaryOp::Minus, Value::Number(n)) => Ok(Value::Number(-n)),
-            _ => Err("Invalid operand for unary operation".to_string()),
+    fn evaluate_logical(&mut self, left: &Expr, operator: &LogicalOp, right: &Expr) -> Result<Value, RuntimeError> {
+        let left_val = self.evaluate_expr(left)?;
+        if let LogicalOp::Coalesce = operator {
+            return match left_val {
+                Value::Nil => self.evaluate_expr(right),
+                value => Ok(value),
+            };
         }
+        // the right operand is only evaluated when it can change the result
+        let left_truthy = self.is_truthy(&left_val);
+        let result = match operator {
+            LogicalOp::And if !left_truthy => false,
+            LogicalOp::Or if left_truthy => true,
+            _ => {
+                let right_val = self.evaluate_expr(right)?;
+                self.is_truthy(&right_val)
+            }
+        };
+        Ok(Value::Boolean(result))
     }
     
-    fn is_truthy(&self, value: &Value) -> bool {
+    fn evaluate_enum_variant(&mut self, enum_name: &str, variant: &str, arguments: &[Expr]) -> Result<Value, RuntimeError> {
+        let def = match self.lookup(enum_name)? {
+            Value::EnumDef(def) => def,
+            other => return Err(RuntimeError::new(ErrorKind::Type, format!("'{}' is not an enum", other))),
+        };
+        let index = def.variant_index(variant)?;
+        let expected = def.variants[index].1.len();
+        if arguments.len() != expected {
+            return Err(format!(
+                "Variant '{}::{}' expected {} values but got {}",
+                def.name,
+                variant,
+                expected,
+                arguments.len()
+            ).into());
+        }
+        let values = self.evaluate_all(arguments)?;
+        Ok(Value::Enum(Rc::new(EnumValue { def, variant: index, values })))
+    }
+    
+    fn evaluate_block(&mut self, statements: &[Stmt], value: Option<&Expr>) -> Result<Value, RuntimeError> {
+        let scope = Rc::new(RefCell::new(Environment::with_enclosing(Rc::clone(&self.environment))));
+        // the parser keeps return/break/continue out of expression blocks
+        self.execute_block(statements, Rc::clone(&scope))?;
         match value {
-            Value::Boolean(b) => *b,
-            Value::Number(n) => *n != 0.0,
-            Value::String(s) => !s.is_empty(),
+            Some(value) => self.evaluate_in_scope(value, scope),
+            None => Ok(Value::Nil),
         }
     }
     
-    // testing
-    #[cfg(test)]
-    pub fn get_variable(&self, name: &str) -> Option<&Value> {
-        self.globals.get(name)
+    fn evaluate_call(&mut self, callee: &Expr, arguments: &[Expr]) -> Result<Value, RuntimeError> {
+        let callee_val = self.evaluate_expr(callee)?;
+        let mut args = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            args.push(self.evaluate_expr(argument)?);
+        }
+        
+        match callee_val {
+            Value::Function(function) => self.call_function(&function, args),
+            Value::NativeFunction(native) => {
+                if args.len() != native.arity {
+                    return Err(format!(
+                        "Function '{}' expected {} arguments but got {}",
+                        native.name,
+                        native.arity,
+                        args.len()
+                    ).into());
+                }
+                match native.function {
+                    NativeFn::Plain(function) => Ok(function(args)?),
+                    NativeFn::WithInterpreter(function) => function(self, args),
+                }
+            }
+            other => Err(RuntimeError::new(ErrorKind::Type, format!("Can only call functions, not '{}'", other))),
+        }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::lexer::Lexer;
-    use crate::parser::Parser;
     
-    #[test]
-    fn test_interpreter_basic() {
-        let mut lexer = Lexer::new("let x = 5; let y = x * 2;".to_string());
-        let tokens = lexer.tokenize();
-        let mut parser = Parser::new(tokens);
-        let ast = parser.parse().unwrap();
-        let mut interpreter = Interpreter::new();
+    fn call_function(&mut self, function: &Function, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        if args.len() != function.params.len() {
+            return Err(format!(
+                "Function '{}' expected {} arguments but got {}",
+                function.display_name(),
+                function.params.len(),
+                args.len()
+            ).into());
+        }
+        if self.call_depth >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::new(
+                ErrorKind::StackOverflow,
+                format!("Stack overflow while calling '{}'", function.display_name()),
+            ));
+        }
         
-        interpreter.interpret(ast).unwrap();
+        let mut scope = Environment::with_enclosing(Rc::clone(&function.closure));
+        for (param, arg) in function.params.iter().zip(args) {
+            scope.define(param.clone(), arg);
+        }
+        
+        // a generator's body does not start until something asks for its first value
+        if function.generator {
+            return Ok(Value::Generator(Rc::new(RefCell::new(Generator {
+                name: function.name.clone(),
+                frames: vec![Frame::Block {
+                    statements: Rc::clone(&function.body),
+                    next: 0,
+                    scope: Rc::new(RefCell::new(scope)),
+                }],
+                running: false,
+            }))));
+        }
         
-        assert!(matches!(interpreter.get_variable("x"), Some(Value::Number(5.0))));
-        assert!(matches!(interpreter.get_variable("y"), Some(Value::Number(10.0))));
+        self.call_depth += 1;
+        let result = self.execute_block(&function.body, Rc::new(RefCell::new(scope)));
+        self.call_depth -= 1;
+        
+        match result? {
+            ControlFlow::Return(value) => Ok(value),
+            // the parser rejects break/continue that would escape a function
+            ControlFlow::Normal | ControlFlow::Break(_) | ControlFlow::Continue(_) => Ok(Value::Nil),
+        }
     }
     
-    #[test]
-    fn test_interpreter_arithmetic() {
-        let mut lexer = Lexer::new("let result = 3 + 4 * 2;".to_string());
-        let tokens = lexer.tokenize();
-        let mut parser = Parser::new(tokens);
-        let ast = parser.parse().unwrap();
-        let mut interpreter = Interpreter::new();
+    /// Runs `statements` inside `scope`, restoring the previous scope afterwards
+    /// even if a statement fails.
+    fn execute_block(&mut self, statements: &[Stmt], scope: Rc<RefCell<Environment>>) -> Result<ControlFlow, RuntimeError> {
+        let previous = std::mem::replace(&mut self.environment, scope);
         
-        interpreter.interpret(ast).unwrap();
+        let mut result = Ok(ControlFlow::Normal);
+        for stmt in statements {
+            match self.execute_stmt(stmt) {
+                Ok(ControlFlow::Normal) => {}
+                other => {
+                    result = other;
+                    break;
+                }
+            }
+        }
         
-        // 3 + (4 * 2) = 11 totally works
-        assert!(matches!(interpreter.get_variable("result"), Some(Value::Number(11.0))));
+        self.environment = previous;
+        result
     }
     
-    #[test]
-    fn test_interpreter_comparison() {
-        let mut lexer = Lexer::new("let result = 5 > 3;".to_string());
-        let tokens = lexer.tokenize();
-        let mut parser = Parser::new(tokens);
-        let ast = parser.parse().unwrap();
-        let mut interpreter = Interpreter::new();
+    /// Runs `generator` until its next `yield`, returning the value yielded, or
+    /// `None` once the generator has finished. A generator that fails or
+    /// returns stays finished.
+    pub fn resume(&mut self, generator: &Rc<RefCell<Generator>>) -> Result<Option<Value>, RuntimeError> {
+        let mut frames = {
+            let mut generator = generator.borrow_mut();
+            if generator.running {
+                return Err(format!("Generator '{}' is already running", generator.display_name()).into());
+            }
+            if self.call_depth >= MAX_CALL_DEPTH {
+                return Err(RuntimeError::new(
+                    ErrorKind::StackOverflow,
+                    format!("Stack overflow while resuming '{}'", generator.display_name()),
+                ));
+            }
+            generator.running = true;
+            std::mem::take(&mut generator.frames)
+        };
         
-        interpreter.interpret(ast).unwrap();
+        let previous = Rc::clone(&self.environment);
+        self.call_depth += 1;
+        let result = self.run_frames(&mut frames);
+        self.call_depth -= 1;
+        self.environment = previous;
         
-        assert!(matches!(interpreter.get_variable("result"), Some(Value::Boolean(true))));
+        let mut generator = generator.borrow_mut();
+        generator.running = false;
+        if let Ok(Some(_)) = result {
+            generator.frames = frames;
+        }
+        result
+    }
+    
+    /// Steps through a generator's frames until one yields or none are left.
+    fn run_frames(&mut self, frames: &mut Vec<Frame>) -> Result<Option<Value>, RuntimeError> {
+        while let Some(frame) = frames.last_mut() {
+            match self.step(frame) {
+                Step::Next => {}
+                Step::Enter(frame) => frames.push(frame),
+                Step::Leave => {
+                    frames.pop();
+                }
+                Step::Yield(value) => return Ok(Some(value)),
+                Step::Exit(exit) => self.unwind(frames, exit)?,
+            }
+        }
+        Ok(None)
+    }
+    
+    /// Advances the innermost frame of a generator: runs the next statement of
+    /// a block, starts the next pass of a loop, or moves on to the next part of
+    /// a `try`.
+    fn step(&mut self, frame: &mut Frame) -> Step {
+        match frame {
+            Frame::Block { statements, next, scope } => {
+                let Some(stmt) = statements.get(*next) else {
+                    return Step::Leave;
+                };
+                *next += 1;
+                self.environment = Rc::clone(scope);
+                let result = if stmt.contains_yield() {
+                    self.enter_stmt(stmt)
+                } else {
+                    self.execute_stmt(stmt).map(|flow| match flow {
+                        ControlFlow::Normal => Step::Next,
+                        flow => Step::Exit(Exit::Flow(flow)),
+                    })
+                };
+                result.unwrap_or_else(|error| Step::Exit(Exit::Error(error)))
+            }
+            Frame::While { condition, body, scope, .. } => {
+                self.environment = Rc::clone(scope);
+                match self.evaluate_expr(condition) {
+                    Ok(value) if self.is_truthy(&value) => Step::Enter(Frame::block(body, scope)),
+                    Ok(_) => Step::Leave,
+                    Err(error) => Step::Exit(Exit::Error(error)),
+                }
+            }
+            Frame::For { var, iteration, body, scope, .. } => {
+                self.environment = Rc::clone(scope);
+                match iteration.next(self) {
+                    Ok(Some(item)) => {
+                        let mut item_scope = Environment::with_enclosing(Rc::clone(scope));
+                        item_scope.define(var.clone(), item);
+                        Step::Enter(Frame::Block {
+                            statements: Rc::clone(body),
+                            next: 0,
+                            scope: Rc::new(RefCell::new(item_scope)),
+                        })
+                    }
+                    Ok(None) => Step::Leave,
+                    Err(error) => Step::Exit(Exit::Error(error)),
+                }
+            }
+            Frame::Try { body, finally, stage, scope, .. } => match stage {
+                TryStage::Start => {
+                    *stage = TryStage::Body;
+                    Step::Enter(Frame::block(body, scope))
+                }
+                TryStage::Body | TryStage::Handler => match finally {
+                    Some(finally) => {
+                        *stage = TryStage::Finally(None);
+                        Step::Enter(Frame::block(finally, scope))
+                    }
+                    None => Step::Leave,
+                },
+                // carry on leaving the way the try was left; unwinding pops this frame
+                TryStage::Finally(pending) => match pending.take() {
+                    Some(exit) => Step::Exit(exit),
+                    None => Step::Leave,
+                },
+            },
+        }
+    }
+    
+    /// Starts a generator statement that contains a `yield`, giving it a frame
+    /// of its own, or yields.
+    fn enter_stmt(&mut self, stmt: &Stmt) -> Result<Step, RuntimeError> {
+        let scope = Rc::clone(&self.environment);
+        let step = match stmt {
+            Stmt::Yield { value, .. } => Step::Yield(self.evaluate_expr(value)?),
+            Stmt::Block(statements) => Step::Enter(Frame::block(&Rc::from(statements.as_slice()), &scope)),
+            Stmt::If { condition, then_stmt, else_stmt } => {
+                let condition_val = self.evaluate_expr(condition)?;
+                let branch = if self.is_truthy(&condition_val) { Some(then_stmt) } else { else_stmt.as_ref() };
+                match branch {
+                    Some(branch) => Step::Enter(Frame::block(&body_statements(branch), &scope)),
+                    None => Step::Next,
+                }
+            }
+            Stmt::While { condition, body, label } => Step::Enter(Frame::While {
+                condition: condition.clone(),
+                body: body_statements(body),
+                label: label.clone(),
+                scope,
+            }),
+            Stmt::For { var, iterable, body, label } => {
+                let iterable_val = self.evaluate_expr(iterable)?;
+                Step::Enter(Frame::For {
+                    var: var.clone(),
+                    iteration: Iteration::new(iterable_val)?,
+                    body: body_statements(body),
+                    label: label.clone(),
+                    scope,
+                })
+            }
+            Stmt::Match { subject, arms } => {
+                let subject_val = self.evaluate_expr(subject)?;
+                let (body, scope) = self.select_arm(&subject_val, arms)?;
+                Step::Enter(Frame::Block { statements: Rc::from(std::slice::from_ref(body)), next: 0, scope })
+            }
+            Stmt::Try { body, catch, finally } => Step::Enter(Frame::Try {
+                body: Rc::from(body.as_slice()),
+                catch: catch.as_ref().map(|(name, handler)| (name.clone(), Rc::from(handler.as_slice()))),
+                finally: finally.as_deref().map(Rc::from),
+                stage: TryStage::Start,
+                scope,
+            }),
+            _ => unreachable!("only compound statements can contain a yield"),
+        };
+        Ok(step)
+    }
+    
+    /// Pops the frames that `exit` leaves: up to the loop a `break` or
+    /// `continue` targets, up to the `try` that catches an error, or all of
+    /// them for a `return`. A `try` with a `finally` on the way runs it first
+    /// and carries on afterwards. Errors that nothing catches are returned.
+    fn unwind(&mut self, frames: &mut Vec<Frame>, exit: Exit) -> Result<(), RuntimeError> {
+        while let Some(frame) = frames.last_mut() {
+            match frame {
+                Frame::While { label, .. } | Frame::For { label, .. } => match &exit {
+                    Exit::Flow(ControlFlow::Break(target)) if ControlFlow::targets_loop(target, label) => {
+                        frames.pop();
+                        return Ok(());
+                    }
+                    // back on top, the loop starts its next pass
+                    Exit::Flow(ControlFlow::Continue(target)) if ControlFlow::targets_loop(target, label) => return Ok(()),
+                    _ => {}
+                },
+                Frame::Try { catch: Some((name, handler)), stage: stage @ TryStage::Body, scope, .. }
+                    if matches!(exit, Exit::Error(_)) =>
+                {
+                    let Exit::Error(error) = exit else {
+                        unreachable!("checked by the guard")
+                    };
+                    *stage = TryStage::Handler;
+                    let mut handler_scope = Environment::with_enclosing(Rc::clone(scope));
+                    handler_scope.define(name.clone(), self.error_value(error));
+                    let handler = Frame::Block {
+                        statements: Rc::clone(handler),
+                        next: 0,
+                        scope: Rc::new(RefCell::new(handler_scope)),
+                    };
+                    frames.push(handler);
+                    return Ok(());
+                }
+                Frame::Try { finally: Some(finally), stage: stage @ (TryStage::Body | TryStage::Handler), scope, .. } => {
+                    let finally = Frame::block(finally, scope);
+                    *stage = TryStage::Finally(Some(exit));
+                    frames.push(finally);
+                    return Ok(());
+                }
+                _ => {}
+            }
+            frames.pop();
+        }
+        
+        match exit {
+            Exit::Error(error) => Err(error),
+            // a return finishes the generator; the parser keeps break and continue inside it
+            Exit::Flow(_) => Ok(()),
+        }
+    }
+    
+    /// Returns the module for `import path;`, running it first unless it has
+    /// already been loaded.
+    fn import(&mut self, path: &str) -> Result<Value, RuntimeError> {
+        let path = self.modules.resolve(path, &self.module_dir)?;
+        if let Some(module) = self.modules.cached(&path) {
+            return Ok(module);
+        }
+        
+        self.modules.begin(&path)?;
+        let module = self.load_module(&path);
+        self.modules.finish(&path, module.as_ref().ok().cloned());
+        module
+    }
+    
+    fn load_module(&mut self, path: &Path) -> Result<Value, RuntimeError> {
+        let file_name = modules::display_name(path);
+        let in_module = |e: String| format!("{} in module '{}'", e, file_name);
+        
+        let source = fs::read_to_string(path).map_err(|e| in_module(e.to_string()))?;
+        let tokens = Lexer::new(source).tokenize().map_err(in_module)?;
+        let statements = Parser::new(tokens).parse().map_err(in_module)?;
+        typecheck::check(&statements).map_err(|errors| in_module(errors.join("; ")))?;
+        
+        // modules see the builtins but not the importer's variables
+        let globals = Rc::new(RefCell::new(Self::globals(&self.error_def)));
+        let scope = Rc::new(RefCell::new(Environment::with_enclosing(globals)));
+        let module_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let importer_dir = std::mem::replace(&mut self.module_dir, module_dir);
+        let result = self.execute_block(&statements, Rc::clone(&scope));
+        self.module_dir = importer_dir;
+        result.map_err(|error| match *error.0 {
+            Failure::Error { kind, message } => RuntimeError::new(kind, in_module(message)),
+            // thrown values reach the importer unchanged so that it can catch them
+            Failure::Thrown(value) => RuntimeError::thrown(value),
+        })?;
+        
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        Ok(Value::Module(Rc::new(Module { name, scope })))
+    }
+    
+    /// The value a `catch` clause binds for `error`: the thrown value itself, or
+    /// an `Error` struct describing an interpreter error.
+    fn error_value(&self, error: RuntimeError) -> Value {
+        match *error.0 {
+            Failure::Thrown(value) => value,
+            Failure::Error { kind, message } => Value::Struct(Rc::new(RefCell::new(StructInstance {
+                def: Rc::clone(&self.error_def),
+                values: vec![Value::String(kind.name().to_string()), Value::String(message)],
+            }))),
+        }
+    }
+    
+    /// Evaluates `expr` inside `scope`, restoring the previous scope afterwards
+    /// even if evaluation fails.
+    fn evaluate_in_scope(&mut self, expr: &Expr, scope: Rc<RefCell<Environment>>) -> Result<Value, RuntimeError> {
+        let previous = std::mem::replace(&mut self.environment, scope);
+        let result = self.evaluate_expr(expr);
+        self.environment = previous;
+        result
     }
-}
\ No newline at end of file
+    
+    /// Finds the first arm whose pattern matches `value` and whose guard holds,
+    /// returning its body and a scope containing the pattern's bindings.
+    fn select_arm<'a, B>(
+        &mut self,
+        value: &Value,
+        arms: &'a [MatchArm<B>],
+    ) -> Result<(&'a B, Rc<RefCell<Environment>>), RuntimeError> {
+        for arm in arms {
+            let mut bindings = Vec::new();
+            if !self.match_pattern(&arm.pattern, value, &mut bindings)? {
+                continue;
+            }
+            
+            let mut scope = Environment::with_enclosing(Rc::clone(&self.environment));
+            for (name, value) in bindings {
+                scope.define(name, value);
+            }
+            let scope = Rc::new(RefCell::new(scope));
+            
+            if let Some(guard) = &arm.guard {
+                let guard_val = self.evaluate_in_scope(guard, Rc::clone(&scope))?;
+                if !self.is_truthy(&guard_val) {
+                    continue;
+                }
+            }
+            return Ok((&arm.body, scope));
+        }
+        
+        Err(format!("No match arm matched {}", value.to_nested_string()).into())
+    }
+    
+    /// Checks `value` against `pattern`, collecting the names it binds.
+    fn match_pattern(&mut self, pattern: &Pattern, value: &Value, bindings: &mut Vec<(String, Value)>) -> Result<bool, RuntimeError> {
+        match pattern {
+            Pattern::Wildcard => Ok(true),
+            Pattern::Binding(name) => {
+                bindings.push((name.clone(), value.clone()));
+                Ok(true)
+            }
+            Pattern::Literal(literal) => {
+                let literal = self.evaluate_expr(literal)?;
+                Ok(self.values_equal(&literal, value))
+            }
+            Pattern::Variant { enum_name, variant, fields } => {
+                let def = match self.lookup(enum_name)? {
+                    Value::EnumDef(def) => def,
+                    other => return Err(RuntimeError::new(ErrorKind::Type, format!("'{}' is not an enum", other))),
+                };
+                let index = def.variant_index(variant)?;
+                let expected = def.variants[index].1.len();
+                if fields.len() != expected {
+                    return Err(format!(
+                        "Pattern for '{}::{}' expected {} fields but got {}",
+                        def.name,
+                        variant,
+                        expected,
+                        fields.len()
+                    ).into());
+                }
+                
+                match value {
+                    Value::Enum(value) if Rc::ptr_eq(&value.def, &def) && value.variant == index => {
+                        for (field, field_val) in fields.iter().zip(&value.values) {
+                            if !self.match_pattern(field, field_val, bindings)? {
+                                return Ok(false);
+                            }
+                        }
+                        Ok(true)
+                    }
+                    _ => Ok(false),
+                }
+            }
+            Pattern::Tuple(patterns) => match value {
+                Value::Tuple(values) if values.len() == patterns.len() => {
+                    for (pattern, value) in patterns.iter().zip(values.iter()) {
+                        if !self.match_pattern(pattern, value, bindings)? {
+                            return Ok(false);
+                        }
+                    }
+                    Ok(true)
+                }
+                _ => Ok(false),
+            },
+        }
+    }
+    
+    /// Collects the names a destructuring `pattern` binds to the parts of
+    /// `value`, failing unless each tuple in the pattern meets a tuple of the
+    /// same length.
+    fn destructure(&self, pattern: &Pattern, value: Value, bindings: &mut Vec<(String, Value)>) -> Result<(), RuntimeError> {
+        match pattern {
+            Pattern::Wildcard => Ok(()),
+            Pattern::Binding(name) => {
+                bindings.push((name.clone(), value));
+                Ok(())
+            }
+            Pattern::Tuple(patterns) => match value {
+                Value::Tuple(values) if values.len() == patterns.len() => {
+                    for (pattern, value) in patterns.iter().zip(values.iter()) {
+                        self.destructure(pattern, value.clone(), bindings)?;
+                    }
+                    Ok(())
+                }
+                Value::Tuple(values) => Err(RuntimeError::new(
+                    ErrorKind::Type,
+                    format!(
+                        "Cannot destructure a tuple of {} values into {} names",
+                        values.len(),
+                        patterns.len()
+                    ),
+                )),
+                other => Err(RuntimeError::new(
+                    ErrorKind::Type,
+                    format!("Expected a tuple of {} values to destructure, got '{}'", patterns.len(), other),
+                )),
+            },
+            Pattern::Literal(_) | Pattern::Variant { .. } => {
+                unreachable!("the parser only destructures into names and tuples")
+            }
+        }
+    }
+    
+    /// `==` that treats values of unrelated types as unequal instead of failing.
+    fn values_equal(&self, left: &Value, right: &Value) -> bool {
+        matches!(
+            self.apply_binary_op(left.clone(), &BinaryOp::Equal, right.clone()),
+            Ok(Value::Boolean(true))
+        )
+    }
+    
+    /// Looks up `object[index]`, returning `None` when the index or key is absent.
+    fn try_get_index(&self, object: &Value, index: &Value) -> Result<Option<Value>, RuntimeError> {
+        match object {
+            Value::List(elements) => {
+                let elements = elements.borrow();
+                Ok(list_index(index, elements.len())?.map(|i| elements[i].clone()))
+            }
+            Value::Tuple(values) => Ok(list_index(index, values.len())?.map(|i| values[i].clone())),
+            Value::String(s) => {
+                let chars: Vec<char> = s.chars().collect();
+                Ok(list_index(index, chars.len())?.map(|i| Value::String(chars[i].to_string())))
+            }
+            Value::Map(entries) => {
+                let key = MapKey::from_value(index)?;
+                Ok(entries.borrow().get(&key).cloned())
+            }
+            other => Err(RuntimeError::new(ErrorKind::Type, format!("Cannot index into '{}'", other))),
+        }
+    }
+    
+    fn get_index(&self, object: Value, index: &Value) -> Result<Value, RuntimeError> {
+        if let Some(value) = self.try_get_index(&object, index)? {
+            return Ok(value);
+        }
+        match object {
+            Value::List(elements) => Err(out_of_bounds(index, elements.borrow().len())),
+            Value::Tuple(values) => Err(out_of_bounds(index, values.len())),
+            Value::String(s) => Err(out_of_bounds(index, s.chars().count())),
+            _ => Err(RuntimeError::new(
+                ErrorKind::KeyNotFound,
+                format!("Key {} not found in map", index.to_nested_string()),
+            )),
+        }
+    }
+    
+    fn set_index(&self, object: Value, index: &Value, value: Value) -> Result<(), RuntimeError> {
+        match object {
+            Value::List(elements) => {
+                let mut elements = elements.borrow_mut();
+                let len = elements.len();
+                let i = list_index(index, len)?.ok_or_else(|| out_of_bounds(index, len))?;
+                elements[i] = value;
+                Ok(())
+            }
+            Value::Map(entries) => {
+                let key = MapKey::from_value(index)?;
+                entries.borrow_mut().insert(key, value);
+                Ok(())
+            }
+            other => Err(RuntimeError::new(ErrorKind::Type, format!("Cannot assign to an index of '{}'", other))),
+        }
+    }
+    
+    /// Reads `object.name`. With `optional` (`?.`), a nil object or a missing map key yields nil.
+    fn get_member(&self, object: Value, name: &str, optional: bool) -> Result<Value, RuntimeError> {
+        match object {
+            Value::Nil if optional => Ok(Value::Nil),
+            Value::Struct(instance) => Ok(instance.borrow().get(name)?),
+            Value::Module(module) => Ok(module.get(name)?),
+            Value::Map(entries) => {
+                let value = entries.borrow().get(&MapKey::String(name.to_string())).cloned();
+                match value {
+                    Some(value) => Ok(value),
+                    None if optional => Ok(Value::Nil),
+                    None => Err(RuntimeError::new(ErrorKind::KeyNotFound, format!("Key {:?} not found in map", name))),
+                }
+            }
+            other => Err(RuntimeError::new(ErrorKind::Type, format!("Cannot read property '{}' of '{}'", name, other))),
+        }
+    }
+    
+    fn set_member(&self, object: Value, name: &str, value: Value) -> Result<(), RuntimeError> {
+        match object {
+            Value::Struct(instance) => Ok(instance.borrow_mut().set(name, value)?),
+            Value::Map(entries) => {
+                entries.borrow_mut().insert(MapKey::String(name.to_string()), value);
+                Ok(())
+            }
+            other => Err(RuntimeError::new(ErrorKind::Type, format!("Cannot set property '{}' of '{}'", name, other))),
+        }
+    }
+    
+    fn define(&mut self, name: String, value: Value) {
+        self.environment.borrow_mut().define(name, value);
+    }
+    
+    fn assign(&mut self, name: &str, value: Value) -> Result<(), RuntimeError> {
+        // the resolver rejects these before running; this is the fallback for programs
+        // that did not go through `Parser::parse` in one piece
+        if self.environment.borrow().is_constant(name) {
+            return Err(RuntimeError::new(ErrorKind::Constant, format!("Cannot assign to constant '{}'", name)));
+        }
+        if self.environment.borrow_mut().assign(name, value) {
+            Ok(())
+        } else {
+            Err(RuntimeError::new(ErrorKind::UndefinedVariable, format!("Undefined variable '{}'", name)))
+        }
+    }
+    
+    fn lookup(&self, name: &str) -> Result<Value, RuntimeError> {
+        self.environment.borrow()
+            .get(name)
+            .ok_or_else(|| RuntimeError::new(ErrorKind::UndefinedVariable, format!("Undefined variable '{}'", name)))
+    }
+    
+    fn apply_binary_op(&self, left: Value, op: &BinaryOp, right: Value) -> Result<Value, RuntimeError> {
+        match (left, right) {
+            (Value::Int(l), Value::Int(r)) => self.apply_int_op(l, op, r),
+            // mixing an int with a float promotes the int to a float
+            (Value::Int(l), Value::Float(r)) => self.apply_float_op(l as f64, op, r),
+            (Value::Float(l), Value::Int(r)) => self.apply_float_op(l, op, r as f64),
+            (Value::Float(l), Value::Float(r)) => self.apply_float_op(l, op, r),
+            (Value::String(l), Value::String(r)) => {
+                match op {
+                    BinaryOp::Add => Ok(Value::String(format!("{}{}", l, r))), // String concatenation
+                    BinaryOp::Equal => Ok(Value::Boolean(l == r)),
+                    BinaryOp::NotEqual => Ok(Value::Boolean(l != r)),
+                    _ => Err(RuntimeError::new(ErrorKind::Type, "Invalid operation for strings")),
+                }
+            }
+            (Value::Boolean(l), Value::Boolean(r)) => {
+                match op {
+                    BinaryOp::Equal => Ok(Value::Boolean(l == r)),
+                    BinaryOp::NotEqual => Ok(Value::Boolean(l != r)),
+                    _ => Err(RuntimeError::new(ErrorKind::Type, "Invalid operation for booleans")),
+                }
+            }
+            (Value::Tuple(l), Value::Tuple(r)) => {
+                let equal = l.len() == r.len() && l.iter().zip(r.iter()).all(|(l, r)| self.values_equal(l, r));
+                match op {
+                    BinaryOp::Equal => Ok(Value::Boolean(equal)),
+                    BinaryOp::NotEqual => Ok(Value::Boolean(!equal)),
+                    _ => Err(RuntimeError::new(ErrorKind::Type, "Invalid operation for tuples")),
+                }
+            }
+            (Value::Enum(l), Value::Enum(r)) => {
+                let equal = Rc::ptr_eq(&l.def, &r.def)
+                    && l.variant == r.variant
+                    && l.values.iter().zip(&r.values).all(|(l, r)| self.values_equal(l, r));
+                match op {
+                    BinaryOp::Equal => Ok(Value::Boolean(equal)),
+                    BinaryOp::NotEqual => Ok(Value::Boolean(!equal)),
+                    _ => Err(RuntimeError::new(ErrorKind::Type, "Invalid operation for enums")),
+                }
+            }
+            // nil is only equal to itself
+            (l @ Value::Nil, r) | (l, r @ Value::Nil) => {
+                let both_nil = matches!((l, r), (Value::Nil, Value::Nil));
+                match op {
+                    BinaryOp::Equal => Ok(Value::Boolean(both_nil)),
+                    BinaryOp::NotEqual => Ok(Value::Boolean(!both_nil)),
+                    _ => Err(RuntimeError::new(ErrorKind::Type, "Invalid operation for nil")),
+                }
+            }
+            // Handle string + number concatenation
+            (Value::String(s), n @ (Value::Int(_) | Value::Float(_))) => {
+                match op {
+                    BinaryOp::Add => Ok(Value::String(format!("{}{}", s, n))),
+                    _ => Err(RuntimeError::new(ErrorKind::Type, "Invalid operation for string and number")),
+                }
+            }
+            // Handle number + string concatenation
+            (n @ (Value::Int(_) | Value::Float(_)), Value::String(s)) => {
+                match op {
+                    BinaryOp::Add => Ok(Value::String(format!("{}{}", n, s))),
+                    _ => Err(RuntimeError::new(ErrorKind::Type, "Invalid operation for number and string")),
+                }
+            }
+            _ => Err(RuntimeError::new(ErrorKind::Type, "Invalid operands for binary operation")),
+        }
+    }
+    
+    fn apply_int_op(&self, l: i64, op: &BinaryOp, r: i64) -> Result<Value, RuntimeError> {
+        let checked = |result: Option<i64>| {
+            result.map(Value::Int).ok_or_else(|| RuntimeError::new(ErrorKind::Overflow, "Integer overflow"))
+        };
+        match op {
+            BinaryOp::Add => checked(l.checked_add(r)),
+            BinaryOp::Subtract => checked(l.checked_sub(r)),
+            BinaryOp::Multiply => checked(l.checked_mul(r)),
+            // integer division truncates toward zero and the remainder takes the sign of `l`
+            BinaryOp::Divide | BinaryOp::Modulo if r == 0 => Err(RuntimeError::new(ErrorKind::DivisionByZero, "Division by zero")),
+            BinaryOp::Divide => checked(l.checked_div(r)),
+            BinaryOp::Modulo => checked(l.checked_rem(r)),
+            // a negative exponent cannot produce an integer
+            BinaryOp::Power if r < 0 => Ok(Value::Float((l as f64).powf(r as f64))),
+            BinaryOp::Power => checked(u32::try_from(r).ok().and_then(|r| l.checked_pow(r))),
+            BinaryOp::BitAnd => Ok(Value::Int(l & r)),
+            BinaryOp::BitOr => Ok(Value::Int(l | r)),
+            BinaryOp::BitXor => Ok(Value::Int(l ^ r)),
+            BinaryOp::ShiftLeft | BinaryOp::ShiftRight if !(0..64).contains(&r) => {
+                Err(RuntimeError::new(ErrorKind::Overflow, format!("Shift amount {} is out of range", r)))
+            }
+            BinaryOp::ShiftLeft => Ok(Value::Int(l << r)),
+            BinaryOp::ShiftRight => Ok(Value::Int(l >> r)),
+            BinaryOp::Equal => Ok(Value::Boolean(l == r)),
+            BinaryOp::NotEqual => Ok(Value::Boolean(l != r)),
+            BinaryOp::Less => Ok(Value::Boolean(l < r)),
+            BinaryOp::Greater => Ok(Value::Boolean(l > r)),
+            BinaryOp::LessEqual => Ok(Value::Boolean(l <= r)),
+            BinaryOp::GreaterEqual => Ok(Value::Boolean(l >= r)),
+        }
+    }
+    
+    fn apply_float_op(&self, l: f64, op: &BinaryOp, r: f64) -> Result<Value, RuntimeError> {
+        match op {
+            BinaryOp::Add => Ok(Value::Float(l + r)),
+            BinaryOp::Subtract => Ok(Value::Float(l - r)),
+            BinaryOp::Multiply => Ok(Value::Float(l * r)),
+            BinaryOp::Divide => {
+                if r == 0.0 {
+                    Err(RuntimeError::new(ErrorKind::DivisionByZero, "Division by zero"))
+                } else {
+                    Ok(Value::Float(l / r))
+                }
+            }
+            BinaryOp::Modulo => {
+                if r == 0.0 {
+                    Err(RuntimeError::new(ErrorKind::DivisionByZero, "Division by zero"))
+                } else {
+                    Ok(Value::Float(l % r))
+                }
+            }
+            BinaryOp::Power => Ok(Value::Float(l.powf(r))),
+            BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor | BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+                Err(RuntimeError::new(ErrorKind::Type, "Bitwise operators require integer operands"))
+            }
+            BinaryOp::Equal => Ok(Value::Boolean((l - r).abs() < f64::EPSILON)),
+            BinaryOp::NotEqual => Ok(Value::Boolean((l - r).abs() >= f64::EPSILON)),
+            BinaryOp::Less => Ok(Value::Boolean(l < r)),
+            BinaryOp::Greater => Ok(Value::Boolean(l > r)),
+            BinaryOp::LessEqual => Ok(Value::Boolean(l <= r)),
+            BinaryOp::GreaterEqual => Ok(Value::Boolean(l >= r)),
+        }
+    }
+    
+    fn apply_unary_op(&self, op: &UnaryOp, operand: Value) -> Result<Value, RuntimeError> {
+        match (op, operand) {
+            (UnaryOp::Minus, Value::Int(n)) => {
+                n.checked_neg().map(Value::Int).ok_or_else(|| RuntimeError::new(ErrorKind::Overflow, "Integer overflow"))
+            }
+            (UnaryOp::Minus, Value::Float(n)) => Ok(Value::Float(-n)),
+            (UnaryOp::Not, value) => Ok(Value::Boolean(!self.is_truthy(&value))),
+            _ => Err(RuntimeError::new(ErrorKind::Type, "Invalid operand for unary operation")),
+        }
+    }
+    
+    fn is_truthy(&self, value: &Value) -> bool {
+        match value {
+            Value::Nil => false,
+            Value::Boolean(b) => *b,
+            Value::Int(n) => *n != 0,
+            Value::Float(n) => *n != 0.0,
+            Value::String(s) => !s.is_empty(),
+            Value::List(elements) => !elements.borrow().is_empty(),
+            Value::Tuple(values) => !values.is_empty(),
+            Value::Map(entries) => !entries.borrow().is_empty(),
+            Value::Range(range) => range.is_before_end(range.start),
+            Value::Function(_) | Value::NativeFunction(_) | Value::StructDef(_) | Value::Struct(_) => true,
+            Value::EnumDef(_) | Value::Enum(_) | Value::Module(_) | Value::Generator(_) => true,
+        }
+    }
+    
+    // testing
+    #[cfg(test)]
+    pub fn get_variable(&self, name: &str) -> Option<Value> {
+        self.environment.borrow().get(name)
+    }
+}
+
+/// The state of a `for` loop over a value, producing one item at a time.
+pub enum Iteration {
+    // `next` is None once stepping would overflow
+    Range { range: Range, next: Option<i64> },
+    // byte offset of the next character
+    Chars { string: String, position: usize },
+    // lists are read live, so elements pushed during the loop are visited
+    List { elements: Rc<RefCell<Vec<Value>>>, index: usize },
+    Tuple { values: Rc<[Value]>, index: usize },
+    Keys(std::vec::IntoIter<Value>),
+    Generator(Rc<RefCell<Generator>>),
+}
+
+impl Iteration {
+    fn new(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::Range(range) => Ok(Iteration::Range { range, next: Some(range.start) }),
+            Value::String(string) => Ok(Iteration::Chars { string, position: 0 }),
+            Value::List(elements) => Ok(Iteration::List { elements, index: 0 }),
+            Value::Tuple(values) => Ok(Iteration::Tuple { values, index: 0 }),
+            Value::Map(entries) => {
+                let keys: Vec<Value> = entries.borrow().keys().map(MapKey::to_value).collect();
+                Ok(Iteration::Keys(keys.into_iter()))
+            }
+            Value::Generator(generator) => Ok(Iteration::Generator(generator)),
+            other => Err(RuntimeError::new(ErrorKind::Type, format!("Cannot iterate over '{}'", other))),
+        }
+    }
+    
+    fn next(&mut self, interpreter: &mut Interpreter) -> Result<Option<Value>, RuntimeError> {
+        match self {
+            Iteration::Generator(generator) => interpreter.resume(generator),
+            other => Ok(other.next_value()),
+        }
+    }
+    
+    /// The next item of anything but a generator, which runs code to produce one.
+    fn next_value(&mut self) -> Option<Value> {
+        match self {
+            Iteration::Range { range, next } => {
+                let current = next.filter(|n| range.is_before_end(*n))?;
+                *next = current.checked_add(range.step);
+                Some(Value::Int(current))
+            }
+            Iteration::Chars { string, position } => {
+                let ch = string[*position..].chars().next()?;
+                *position += ch.len_utf8();
+                Some(Value::String(ch.to_string()))
+            }
+            Iteration::List { elements, index } => {
+                let item = elements.borrow().get(*index).cloned()?;
+                *index += 1;
+                Some(item)
+            }
+            Iteration::Tuple { values, index } => {
+                let item = values.get(*index).cloned()?;
+                *index += 1;
+                Some(item)
+            }
+            Iteration::Keys(keys) => keys.next(),
+            Iteration::Generator(_) => unreachable!("generators are resumed by `next`"),
+        }
+    }
+}
+
+/// Converts an index value into a position within a sequence of length `len`,
+/// or `None` if it is past the end.
+fn list_index(index: &Value, len: usize) -> Result<Option<usize>, String> {
+    match index {
+        Value::Int(n) if *n >= 0 => {
+            let i = *n as usize;
+            Ok((i < len).then_some(i))
+        }
+        other => Err(format!("Index must be a non-negative integer, got '{}'", other)),
+    }
+}
+
+fn out_of_bounds(index: &Value, len: usize) -> RuntimeError {
+    RuntimeError::new(ErrorKind::IndexOutOfBounds, format!("Index {} out of bounds for length {}", index, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    
+    #[test]
+    fn test_interpreter_basic() {
+        let mut lexer = Lexer::new("let x = 5; let y = x * 2;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("x"), Some(Value::Int(5))));
+        assert!(matches!(interpreter.get_variable("y"), Some(Value::Int(10))));
+    }
+    
+    #[test]
+    fn test_interpreter_arithmetic() {
+        let mut lexer = Lexer::new("let result = 3 + 4 * 2;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        // 3 + (4 * 2) = 11 totally works
+        assert!(matches!(interpreter.get_variable("result"), Some(Value::Int(11))));
+    }
+    
+    #[test]
+    fn test_interpreter_int_and_float() {
+        let source = "
+            let quotient = 7 / 2;
+            let negative = -7 / 2;
+            let mixed = 7 / 2.0;
+            let big = 9007199254740993 + 0;
+            let label = \"n=\" + 3 + \", x=\" + 3.0;
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("quotient"), Some(Value::Int(3))));
+        assert!(matches!(interpreter.get_variable("negative"), Some(Value::Int(-3))));
+        assert!(matches!(interpreter.get_variable("mixed"), Some(Value::Float(3.5))));
+        // beyond 2^53, where f64 would have rounded
+        assert!(matches!(interpreter.get_variable("big"), Some(Value::Int(9007199254740993))));
+        assert!(matches!(interpreter.get_variable("label"), Some(Value::String(s)) if s == "n=3, x=3.0"));
+    }
+    
+    #[test]
+    fn test_interpreter_modulo_power_bitwise() {
+        let source = "
+            let rem = -7 % 3;
+            let even = 10 % 2 == 0;
+            let pow = 2 ** 3 ** 2;
+            let neg = -2 ** 2;
+            let inverse = 2 ** -1;
+            let bits = (12 & 10) | (1 << 4) ^ 3;
+            let shifted = -16 >> 2;
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("rem"), Some(Value::Int(-1))));
+        assert!(matches!(interpreter.get_variable("even"), Some(Value::Boolean(true))));
+        assert!(matches!(interpreter.get_variable("pow"), Some(Value::Int(512))));
+        assert!(matches!(interpreter.get_variable("neg"), Some(Value::Int(-4))));
+        assert!(matches!(interpreter.get_variable("inverse"), Some(Value::Float(0.5))));
+        assert!(matches!(interpreter.get_variable("bits"), Some(Value::Int(27))));
+        assert!(matches!(interpreter.get_variable("shifted"), Some(Value::Int(-4))));
+    }
+    
+    #[test]
+    fn test_interpreter_bitwise_requires_integers() {
+        let mut lexer = Lexer::new("let x = 1.5 & 1;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        assert_eq!(interpreter.interpret(ast).unwrap_err(), "Bitwise operators require integer operands");
+    }
+    
+    #[test]
+    fn test_interpreter_compound_assignment() {
+        let source = "
+            let i = 10;
+            i += 5;
+            i -= 3;
+            i *= 2;
+            i /= 5;
+            i %= 3;
+            let greeting = \"Hello\";
+            greeting += \", Ferris\";
+            let counts = {\"a\": 1};
+            counts[\"a\"] += 41;
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("i"), Some(Value::Int(1))));
+        assert!(matches!(interpreter.get_variable("greeting"), Some(Value::String(s)) if s == "Hello, Ferris"));
+        let Some(Value::Map(counts)) = interpreter.get_variable("counts") else {
+            panic!("expected counts to be a map");
+        };
+        assert!(matches!(counts.borrow().get(&MapKey::String("a".to_string())), Some(Value::Int(42))));
+    }
+    
+    #[test]
+    fn test_interpreter_compound_assignment_undefined() {
+        let mut lexer = Lexer::new("missing += 1;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        assert_eq!(interpreter.interpret(ast).unwrap_err(), "Undefined variable 'missing'");
+    }
+    
+    #[test]
+    fn test_interpreter_integer_overflow() {
+        let mut lexer = Lexer::new("let x = 9223372036854775807 + 1;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        assert_eq!(interpreter.interpret(ast).unwrap_err(), "Integer overflow");
+    }
+    
+    #[test]
+    fn test_interpreter_comparison() {
+        let mut lexer = Lexer::new("let result = 5 > 3;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("result"), Some(Value::Boolean(true))));
+    }
+    
+    #[test]
+    fn test_interpreter_short_circuit() {
+        // the right-hand sides would fail with an undefined variable if evaluated
+        let mut lexer = Lexer::new("let a = false && missing; let b = true || missing; let c = !a && b;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("a"), Some(Value::Boolean(false))));
+        assert!(matches!(interpreter.get_variable("b"), Some(Value::Boolean(true))));
+        assert!(matches!(interpreter.get_variable("c"), Some(Value::Boolean(true))));
+    }
+    
+    #[test]
+    fn test_interpreter_lists() {
+        let source = "
+            let xs = [1, 2, 3];
+            let ys = xs;
+            ys[0] = 10;
+            push(xs, 4);
+            let first = xs[0];
+            let count = len(xs);
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("first"), Some(Value::Int(10))));
+        assert!(matches!(interpreter.get_variable("count"), Some(Value::Int(4))));
+    }
+    
+    #[test]
+    fn test_interpreter_self_containing_values() {
+        let source = "
+            let xs = [1];
+            push(xs, xs);
+            let m = {\"items\": xs};
+            m[\"self\"] = m;
+            struct Node { next }
+            let node = Node { next: nil };
+            node.next = node;
+            let pair = [node, node];
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert_eq!(interpreter.get_variable("xs").unwrap().to_string(), "[1, [...]]");
+        assert_eq!(interpreter.get_variable("m").unwrap().to_string(), "{\"items\": [1, [...]], \"self\": {...}}");
+        assert_eq!(interpreter.get_variable("node").unwrap().to_string(), "Node { next: Node { ... } }");
+        // a value repeated side by side is not a cycle
+        assert_eq!(
+            interpreter.get_variable("pair").unwrap().to_string(),
+            "[Node { next: Node { ... } }, Node { next: Node { ... } }]"
+        );
+    }
+    
+    #[test]
+    fn test_interpreter_maps() {
+        let source = "
+            let m = {\"name\": \"ferris\", \"legs\": 10};
+            m[\"legs\"] = m[\"legs\"] - 2;
+            m[1] = true;
+            let legs = m[\"legs\"];
+            let found = has(m, \"name\") && !has(m, \"tail\");
+            let count = len(keys(m));
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("legs"), Some(Value::Int(8))));
+        assert!(matches!(interpreter.get_variable("found"), Some(Value::Boolean(true))));
+        assert!(matches!(interpreter.get_variable("count"), Some(Value::Int(3))));
+    }
+    
+    #[test]
+    fn test_interpreter_for_loops() {
+        let source = "
+            let sum = 0;
+            for i in 1..=10 { sum = sum + i; }
+            let evens = 0;
+            for i in 10..0 step -2 { evens = evens + 1; }
+            let letters = \"\";
+            for ch in \"abc\" { letters = ch + letters; }
+            let total = 0;
+            for x in [1, 2, 3] { total = total + x; }
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("sum"), Some(Value::Int(55))));
+        assert!(matches!(interpreter.get_variable("evens"), Some(Value::Int(5))));
+        assert!(matches!(interpreter.get_variable("letters"), Some(Value::String(s)) if s == "cba"));
+        assert!(matches!(interpreter.get_variable("total"), Some(Value::Int(6))));
+        // the loop variable is scoped to the loop
+        assert!(interpreter.get_variable("i").is_none());
+    }
+    
+    #[test]
+    fn test_interpreter_break_continue() {
+        let source = "
+            let odd_sum = 0;
+            for i in 0..100 {
+                if (i > 9) { break; }
+                if (i == 0 || i == 2 || i == 4 || i == 6 || i == 8) { continue; }
+                odd_sum = odd_sum + i;
+            }
+            let found = 0;
+            'outer: for a in 1..10 {
+                let b = 1;
+                while (b < 10) {
+                    if (a * b == 42) {
+                        found = a * 10 + b;
+                        break 'outer;
+                    }
+                    b = b + 1;
+                }
+            }
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("odd_sum"), Some(Value::Int(25))));
+        assert!(matches!(interpreter.get_variable("found"), Some(Value::Int(67))));
+    }
+    
+    #[test]
+    fn test_interpreter_index_out_of_bounds() {
+        let mut lexer = Lexer::new("let xs = [1, 2]; let x = xs[2];".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        let err = interpreter.interpret(ast).unwrap_err();
+        assert_eq!(err, "Index 2 out of bounds for length 2");
+    }
+    
+    #[test]
+    fn test_interpreter_nil() {
+        let source = "
+            let unset;
+            fn nothing() {}
+            let config = {\"user\": {\"name\": \"ferris\"}, \"tags\": [\"crab\"]};
+            let name = config?.user?.name ?? \"anonymous\";
+            let missing = config?.owner?.name ?? \"anonymous\";
+            let tag = config.tags?.[5] ?? \"none\";
+            let is_nil = unset == nil && nothing() == nil && !unset;
+            let zero = 0 ?? 1;
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("unset"), Some(Value::Nil)));
+        assert!(matches!(interpreter.get_variable("name"), Some(Value::String(s)) if s == "ferris"));
+        assert!(matches!(interpreter.get_variable("missing"), Some(Value::String(s)) if s == "anonymous"));
+        assert!(matches!(interpreter.get_variable("tag"), Some(Value::String(s)) if s == "none"));
+        assert!(matches!(interpreter.get_variable("is_nil"), Some(Value::Boolean(true))));
+        // only nil is replaced, not other falsy values
+        assert!(matches!(interpreter.get_variable("zero"), Some(Value::Int(0))));
+    }
+    
+    #[test]
+    fn test_interpreter_string_interpolation() {
+        let source = r#"
+            let name = "Ferris";
+            let age = 7;
+            let xs = [1, "two"];
+            let message = "Hello, {name}! You are {age + 1}. {xs} {nil} {true} {{braces}} {"in" + "ner"}";
+        "#;
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(
+            interpreter.get_variable("message"),
+            Some(Value::String(s)) if s == r#"Hello, Ferris! You are 8. [1, "two"] nil true {braces} inner"#
+        ));
+    }
+    
+    #[test]
+    fn test_interpreter_structs() {
+        let source = "
+            struct Point { x, y }
+            let p = Point { y: 2, x: 1 };
+            let alias = p;
+            alias.x = 3;
+            p.y += 10;
+            let sum = p.x + p.y;
+            let shown = \"\" + p.x;
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("sum"), Some(Value::Int(15))));
+        assert_eq!(interpreter.get_variable("p").unwrap().to_string(), "Point { x: 3, y: 12 }");
+    }
+    
+    #[test]
+    fn test_interpreter_struct_errors() {
+        let cases = [
+            ("struct P { x } let p = P { x: 1 }; let z = p.z;", "Struct 'P' has no field 'z'"),
+            ("struct P { x } let p = P { x: 1 }; p.z = 2;", "Struct 'P' has no field 'z'"),
+            ("struct P { x, y } let p = P { x: 1 };", "Missing field 'y' in 'P' initializer"),
+        ];
+        for (source, expected) in cases {
+            let mut lexer = Lexer::new(source.to_string());
+            let tokens = lexer.tokenize().unwrap();
+            let mut parser = Parser::new(tokens);
+            let ast = parser.parse().unwrap();
+            let mut interpreter = Interpreter::new();
+            
+            assert_eq!(interpreter.interpret(ast).unwrap_err(), expected);
+        }
+    }
+    
+    #[test]
+    fn test_interpreter_conditional_expressions() {
+        let source = "
+            let t = 75;
+            let label = if (t > 80) { \"hot\" } else if (t > 60) { \"warm\" } else { \"cold\" };
+            let sign = t < 0 ? \"negative\" : t == 0 ? \"zero\" : \"positive\";
+            let missing = if (t < 0) { 1 };
+            let calls = 0;
+            fn bump() { calls += 1; return calls; }
+            let picked = true ? 1 : bump();
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("label"), Some(Value::String(s)) if s == "warm"));
+        assert!(matches!(interpreter.get_variable("sign"), Some(Value::String(s)) if s == "positive"));
+        assert!(matches!(interpreter.get_variable("missing"), Some(Value::Nil)));
+        // only the chosen branch is evaluated
+        assert!(matches!(interpreter.get_variable("picked"), Some(Value::Int(1))));
+        assert!(matches!(interpreter.get_variable("calls"), Some(Value::Int(0))));
+    }
+    
+    #[test]
+    fn test_interpreter_enums_and_match() {
+        let source = "
+            enum Shape { Circle(r), Rect(w, h), Empty }
+            fn area(shape) {
+                return match shape {
+                    Shape::Circle(r) => 3 * r * r,
+                    Shape::Rect(w, h) if w == h => { let side = w; side * side }
+                    Shape::Rect(w, h) => w * h,
+                    Shape::Empty => 0,
+                };
+            }
+            let total = area(Shape::Circle(2)) + area(Shape::Rect(3, 3)) + area(Shape::Rect(2, 5)) + area(Shape::Empty);
+            let same = Shape::Rect(1, 2) == Shape::Rect(1, 2);
+            let label = \"\";
+            for n in 0..3 {
+                match n {
+                    0 => { label += \"zero \"; }
+                    \"0\" => { label += \"string \"; }
+                    n if n % 2 == 1 => { label += \"odd \"; continue; }
+                    _ => { label += \"even\"; }
+                }
+            }
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("total"), Some(Value::Int(31))));
+        assert!(matches!(interpreter.get_variable("same"), Some(Value::Boolean(true))));
+        assert!(matches!(interpreter.get_variable("label"), Some(Value::String(s)) if s == "zero odd even"));
+    }
+    
+    #[test]
+    fn test_interpreter_match_errors() {
+        let cases = [
+            ("let x = match 3 { 1 => 1, 2 => 2 };", "No match arm matched 3"),
+            ("enum E { A(x) } let x = E::A;", "Variant 'E::A' expected 1 values but got 0"),
+            ("enum E { A } let x = E::B;", "Enum 'E' has no variant 'B'"),
+        ];
+        for (source, expected) in cases {
+            let mut lexer = Lexer::new(source.to_string());
+            let tokens = lexer.tokenize().unwrap();
+            let mut parser = Parser::new(tokens);
+            let ast = parser.parse().unwrap();
+            let mut interpreter = Interpreter::new();
+            
+            assert_eq!(interpreter.interpret(ast).unwrap_err(), expected);
+        }
+    }
+    
+    #[test]
+    fn test_interpreter_closures() {
+        let source = "
+            fn make_counter() {
+                let count = 0;
+                return fn() { count += 1; return count; };
+            }
+            let counter = make_counter();
+            counter();
+            let second = counter();
+            let other = make_counter()();
+            
+            let total = 0;
+            let add_to_total = fn(n) { total += n; };
+            add_to_total(5);
+            add_to_total(6);
+            
+            let twice = |f, x| f(f(x));
+            let result = twice(|x| x * 3, 2);
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("second"), Some(Value::Int(2))));
+        assert!(matches!(interpreter.get_variable("other"), Some(Value::Int(1))));
+        // the closure assigned to the variable it captured, not a copy
+        assert!(matches!(interpreter.get_variable("total"), Some(Value::Int(11))));
+        assert!(matches!(interpreter.get_variable("result"), Some(Value::Int(18))));
+        assert_eq!(interpreter.get_variable("add_to_total").unwrap().to_string(), "<fn>");
+    }
+    
+    #[test]
+    fn test_interpreter_functions() {
+        let source = "
+            fn fact(n) {
+                if (n <= 1) {
+                    return 1;
+                }
+                return n * fact(n - 1);
+            }
+            let result = fact(5);
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("result"), Some(Value::Int(120))));
+        // parameters and locals must not leak out of the call
+        assert!(interpreter.get_variable("n").is_none());
+    }
+    
+    /// Runs `test` on a thread with the stack `main` gives the interpreter,
+    /// since the test harness's threads have less.
+    fn with_interpreter_stack<T: Send + 'static>(test: impl FnOnce() -> T + Send + 'static) -> T {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(test)
+            .unwrap()
+            .join()
+            .unwrap()
+    }
+    
+    /// Runs `source` with the interpreter's stack, returning the final value of `name`.
+    fn run_deep(source: String, name: &'static str) -> String {
+        with_interpreter_stack(move || {
+            let mut lexer = Lexer::new(source);
+            let tokens = lexer.tokenize().unwrap();
+            let mut parser = Parser::new(tokens);
+            let ast = parser.parse().unwrap();
+            let mut interpreter = Interpreter::new();
+            
+            interpreter.interpret(ast).unwrap();
+            interpreter.get_variable(name).unwrap().to_string()
+        })
+    }
+    
+    // the recursive call sits inside a loop and a branch, as it often does in scripts
+    const DEPTH: &str = "
+        fn depth(n) {
+            while (true) {
+                if (n == 1) { return 1; }
+                return 1 + depth(n - 1);
+            }
+        }
+    ";
+    
+    #[test]
+    fn test_interpreter_recursion_up_to_limit() {
+        let source = format!("{} let reached = depth({});", DEPTH, MAX_CALL_DEPTH);
+        
+        assert_eq!(run_deep(source, "reached"), MAX_CALL_DEPTH.to_string());
+    }
+    
+    #[test]
+    fn test_interpreter_recursion_past_limit() {
+        let source = format!(
+            "{} let kind = nil; try {{ depth({}); }} catch (e) {{ kind = e.kind; }}",
+            DEPTH,
+            MAX_CALL_DEPTH + 1
+        );
+        
+        assert_eq!(run_deep(source, "kind"), "StackOverflow");
+    }
+    
+    #[test]
+    fn test_interpreter_block_scoping() {
+        let source = "
+            let x = 1;
+            let y = 1;
+            {
+                let x = 2;
+                let inner = x;
+                y = x + 1;
+            }
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        // the inner `let x` shadows, while assignment to `y` reaches the outer binding
+        assert!(matches!(interpreter.get_variable("x"), Some(Value::Int(1))));
+        assert!(matches!(interpreter.get_variable("y"), Some(Value::Int(3))));
+        assert!(interpreter.get_variable("inner").is_none());
+    }
+    
+    #[test]
+    fn test_interpreter_function_arity() {
+        let mut lexer = Lexer::new("fn f(a) { return a; } let x = f(1, 2);".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        assert!(interpreter.interpret(ast).is_err());
+    }
+    
+    /// Writes `files` into a fresh directory under the system temp dir.
+    fn module_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ferris-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (file, source) in files {
+            fs::write(dir.join(file), source).unwrap();
+        }
+        dir
+    }
+    
+    #[test]
+    fn test_interpreter_imports() {
+        let dir = module_dir("imports", &[
+            ("util.ferris", "let loads = 1; fn double(x) { return x * 2; }"),
+            ("main.ferris", "import util; import \"util.ferris\"; let result = util.double(21); let loads = util.loads;"),
+        ]);
+        let script = dir.join("main.ferris");
+        let mut lexer = Lexer::new(fs::read_to_string(&script).unwrap());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::with_modules(Some(&script), Vec::new());
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("result"), Some(Value::Int(42))));
+        assert!(matches!(interpreter.get_variable("loads"), Some(Value::Int(1))));
+        // module members stay behind the module name
+        assert!(interpreter.get_variable("double").is_none());
+    }
+    
+    #[test]
+    fn test_interpreter_import_search_path_and_cycles() {
+        let dir = module_dir("cycles", &[
+            ("a.ferris", "import b;"),
+            ("b.ferris", "import a;"),
+        ]);
+        
+        let mut lexer = Lexer::new("import a;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::with_modules(None, vec![dir]);
+        
+        let error = interpreter.interpret(ast).unwrap_err();
+        assert!(error.starts_with("Circular import: a.ferris -> b.ferris -> a.ferris"), "{}", error);
+    }
+    
+    #[test]
+    fn test_interpreter_try_catch_finally() {
+        let source = "
+            let log = [];
+            fn check(n) {
+                if (n < 0) { throw \"negative\"; }
+                return n;
+            }
+            try { check(-1); } catch (e) { push(log, e); } finally { push(log, \"cleanup\"); }
+            let kind = nil;
+            let message = nil;
+            try { let x = 1 / 0; } catch (e) { kind = e.kind; message = e.message; }
+            fn early() {
+                try { return 1; } finally { push(log, \"finally\"); }
+            }
+            let returned = early();
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        let log = interpreter.get_variable("log").unwrap().to_string();
+        assert_eq!(log, r#"["negative", "cleanup", "finally"]"#);
+        assert!(matches!(interpreter.get_variable("kind"), Some(Value::String(s)) if s == "DivisionByZero"));
+        assert!(matches!(interpreter.get_variable("message"), Some(Value::String(s)) if s == "Division by zero"));
+        assert!(matches!(interpreter.get_variable("returned"), Some(Value::Int(1))));
+    }
+    
+    #[test]
+    fn test_interpreter_uncaught_throw() {
+        let mut lexer = Lexer::new("try { throw 1; } finally { let cleaned = true; }".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        assert_eq!(interpreter.interpret(ast).unwrap_err(), "Uncaught exception: 1");
+    }
+    
+    #[test]
+    fn test_interpreter_constant_guard() {
+        let parse = |source: &str| Parser::new(Lexer::new(source.to_string()).tokenize().unwrap()).parse();
+        assert_eq!(
+            parse("fn bump() { LIMIT = 2; } const LIMIT = 1; bump();").unwrap_err(),
+            "Cannot assign to constant 'LIMIT' at line 1"
+        );
+        
+        // each half goes through `Parser::parse` on its own, so only the runtime sees both
+        let mut interpreter = Interpreter::new();
+        interpreter.interpret(parse("fn bump() { LIMIT = 2; }").unwrap()).unwrap();
+        assert_eq!(
+            interpreter.interpret(parse("const LIMIT = 1; bump();").unwrap()).unwrap_err(),
+            "Cannot assign to constant 'LIMIT'"
+        );
+        assert!(matches!(interpreter.get_variable("LIMIT"), Some(Value::Int(1))));
+    }
+    
+    #[test]
+    fn test_interpreter_generators() {
+        let source = "
+            let log = [];
+            fn count(limit) {
+                push(log, \"started\");
+                let i = 0;
+                while (true) {
+                    if (i == limit) { return; }
+                    yield i;
+                    i += 1;
+                }
+            }
+            let numbers = count(3);
+            let before = len(log);
+            let seen = [];
+            for n in numbers { push(seen, n); }
+            let after = next(numbers);
+            
+            fn evens() {
+                for i in 0..10 {
+                    if (i % 2 == 1) { continue; }
+                    if (i > 4) { break; }
+                    yield i;
+                }
+            }
+            let g = evens();
+            let first = next(g);
+            let rest = [];
+            for n in g { push(rest, n); }
+            
+            fn guarded() {
+                try {
+                    yield 1;
+                    throw \"boom\";
+                } catch (e) {
+                    yield e;
+                } finally {
+                    push(log, \"finally\");
+                }
+            }
+            let caught = [];
+            for v in guarded() { push(caught, v); }
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        // nothing runs until the first value is wanted
+        assert!(matches!(interpreter.get_variable("before"), Some(Value::Int(0))));
+        assert_eq!(interpreter.get_variable("seen").unwrap().to_string(), "[0, 1, 2]");
+        assert!(matches!(interpreter.get_variable("after"), Some(Value::Nil)));
+        assert!(matches!(interpreter.get_variable("first"), Some(Value::Int(0))));
+        assert_eq!(interpreter.get_variable("rest").unwrap().to_string(), "[2, 4]");
+        assert_eq!(interpreter.get_variable("caught").unwrap().to_string(), r#"[1, "boom"]"#);
+        assert_eq!(interpreter.get_variable("log").unwrap().to_string(), r#"["started", "finally"]"#);
+    }
+    
+    #[test]
+    fn test_interpreter_generator_errors() {
+        let source = "
+            fn fails() { yield 1; let x = 1 / 0; }
+            let g = fails();
+            let first = next(g);
+            let kind = nil;
+            try { next(g); } catch (e) { kind = e.kind; }
+            let finished = next(g);
+            let misuse = nil;
+            try { next([1]); } catch (e) { misuse = e.message; }
+            fn selfish() { yield next(me); }
+            let me = selfish();
+            next(me);
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        assert_eq!(interpreter.interpret(ast).unwrap_err(), "Generator 'selfish' is already running");
+        assert!(matches!(interpreter.get_variable("first"), Some(Value::Int(1))));
+        assert!(matches!(interpreter.get_variable("kind"), Some(Value::String(s)) if s == "DivisionByZero"));
+        // a generator that failed stays finished
+        assert!(matches!(interpreter.get_variable("finished"), Some(Value::Nil)));
+        assert!(matches!(interpreter.get_variable("misuse"), Some(Value::String(s)) if s == "next() expects a generator, got '[1]'"));
+    }
+    
+    #[test]
+    fn test_interpreter_tuples() {
+        let source = "
+            fn divmod(a, b) { return (a / b, a % b); }
+            let (q, r) = divmod(7, 2);
+            let a = 1;
+            let b = 2;
+            (a, b) = (b, a);
+            let (first, (_, last)) = (\"x\", (true, nil));
+            let single = (5,);
+            let point = (3, 4);
+            let sum = point[0] + point[1];
+            let same = point == (3, 4);
+            let kind = match point {
+                (0, 0) => \"origin\",
+                (x, _) if x > 0 => \"right\",
+                _ => \"left\",
+            };
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        
+        interpreter.interpret(ast).unwrap();
+        
+        assert!(matches!(interpreter.get_variable("q"), Some(Value::Int(3))));
+        assert!(matches!(interpreter.get_variable("r"), Some(Value::Int(1))));
+        assert!(matches!(interpreter.get_variable("a"), Some(Value::Int(2))));
+        assert!(matches!(interpreter.get_variable("b"), Some(Value::Int(1))));
+        assert!(matches!(interpreter.get_variable("first"), Some(Value::String(s)) if s == "x"));
+        assert!(matches!(interpreter.get_variable("last"), Some(Value::Nil)));
+        assert_eq!(interpreter.get_variable("single").unwrap().to_string(), "(5,)");
+        assert!(matches!(interpreter.get_variable("sum"), Some(Value::Int(7))));
+        assert!(matches!(interpreter.get_variable("same"), Some(Value::Boolean(true))));
+        assert!(matches!(interpreter.get_variable("kind"), Some(Value::String(s)) if s == "right"));
+    }
+    
+    #[test]
+    fn test_interpreter_destructuring_arity() {
+        for (source, message) in [
+            ("let (a, b) = (1, 2, 3);", "Cannot destructure a tuple of 3 values into 2 names"),
+            ("let (a, (b, c)) = (1, (2,));", "Cannot destructure a tuple of 1 values into 2 names"),
+            ("let a = 0; let b = 0; (a, b) = [1, 2];", "Expected a tuple of 2 values to destructure, got '[1, 2]'"),
+        ] {
+            let mut lexer = Lexer::new(source.to_string());
+            let tokens = lexer.tokenize().unwrap();
+            let mut parser = Parser::new(tokens);
+            let ast = parser.parse().unwrap();
+            let mut interpreter = Interpreter::new();
+            
+            assert_eq!(interpreter.interpret(ast).unwrap_err(), message);
+        }
+    }
+}
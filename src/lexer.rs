@@ -1,4 +1,4 @@
-use crate::token::{Token, TokenType};
+use crate::token::{StringPart, Token, TokenType};
 
 pub struct Lexer {
     input: Vec<char>,
@@ -32,19 +32,22 @@ impl Lexer {
         ch
     }
     
-    fn skip_whitespace(&mut self) {
+    fn skip_whitespace(&mut self) -> Result<(), String> {
         while let Some(ch) = self.peek() {
             if ch.is_whitespace() {
                 self.advance();
-            } else if ch == '/' && self.peek_next() == Some('/') {
+            } else if ch == '/' && self.peek_next() == Some('/') && !self.at_doc_comment() {
                 // Skip line comment
                 self.advance(); // Skip first '/'
                 self.advance(); // Skip second '/'
                 self.skip_line_comment();
+            } else if ch == '/' && self.peek_next() == Some('*') {
+                self.skip_block_comment()?;
             } else {
                 break;
             }
         }
+        Ok(())
     }
     
     fn skip_line_comment(&mut self) {
@@ -57,11 +60,68 @@ impl Lexer {
         }
     }
     
-    fn read_number(&mut self) -> f64 {
+    /// Skips a `/* ... */` comment, which may contain nested block comments.
+    fn skip_block_comment(&mut self) -> Result<(), String> {
+        let line = self.line;
+        let mut depth = 0;
+        
+        loop {
+            match (self.peek(), self.peek_next()) {
+                (Some('/'), Some('*')) => {
+                    self.advance();
+                    self.advance();
+                    depth += 1;
+                }
+                (Some('*'), Some('/')) => {
+                    self.advance();
+                    self.advance();
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                (Some(_), _) => {
+                    self.advance();
+                }
+                (None, _) => return Err(format!("Unterminated block comment starting at line {}", line)),
+            }
+        }
+    }
+    
+    /// Whether the input continues with `///` but not `////`, which is an ordinary comment.
+    fn at_doc_comment(&self) -> bool {
+        let rest = &self.input[self.position..];
+        rest.starts_with(&['/', '/', '/']) && rest.get(3) != Some(&'/')
+    }
+    
+    fn read_doc_comment(&mut self) -> String {
+        for _ in 0..3 {
+            self.advance();
+        }
+        if self.peek() == Some(' ') {
+            self.advance();
+        }
+        
+        let mut text = String::new();
+        while let Some(ch) = self.peek() {
+            if ch == '\n' {
+                break;
+            }
+            text.push(ch);
+            self.advance();
+        }
+        text.trim_end().to_string()
+    }
+    
+    fn read_number(&mut self) -> Result<TokenType, String> {
         let mut number = String::new();
         
         while let Some(ch) = self.peek() {
-            if ch.is_ascii_digit() || ch == '.' {
+            // a '.' only belongs to the number when a digit follows, so `0..10` stays a range
+            let is_fraction = ch == '.'
+                && !number.contains('.')
+                && self.peek_next().is_some_and(|next| next.is_ascii_digit());
+            if ch.is_ascii_digit() || is_fraction {
                 number.push(ch);
                 self.advance();
             } else {
@@ -69,7 +129,14 @@ impl Lexer {
             }
         }
         
-        number.parse().unwrap_or(0.0)
+        if number.contains('.') {
+            Ok(TokenType::Float(number.parse().unwrap_or(0.0)))
+        } else {
+            number
+                .parse()
+                .map(TokenType::Integer)
+                .map_err(|_| format!("Integer literal '{}' is too large at line {}", number, self.line))
+        }
     }
     
     fn read_identifier(&mut self) -> String {
@@ -87,73 +154,285 @@ impl Lexer {
         identifier
     }
     
-    fn read_string(&mut self) -> String {
+    /// Reads a `"..."` string literal.
+    fn read_string(&mut self) -> Result<TokenType, String> {
+        let line = self.line;
+        self.advance(); // Skip opening quote
+        self.read_string_contents(Some('"'), line)
+    }
+    
+    /// Reads the contents of a string literal up to the `closing` quote, or to the
+    /// end of the input when there is none. `{expression}` segments are tokenized
+    /// separately, and `{{` / `}}` stand for literal braces.
+    fn read_string_contents(&mut self, closing: Option<char>, start_line: usize) -> Result<TokenType, String> {
+        let mut parts = Vec::new();
         let mut string = String::new();
         
-        // Skip opening quote
-        self.advance();
-        
-        while let Some(ch) = self.peek() {
-            if ch == '"' {
+        loop {
+            let Some(ch) = self.peek() else {
+                if closing.is_some() {
+                    return Err(format!("Unterminated string starting at line {}", start_line));
+                }
+                break;
+            };
+            
+            if Some(ch) == closing {
                 self.advance(); // Skip closing quote
                 break;
             } else if ch == '\\' {
-                // Handle escape sequences
                 self.advance(); // Skip backslash
-                if let Some(escaped) = self.peek() {
-                    match escaped {
-                        'n' => string.push('\n'),
-                        't' => string.push('\t'),
-                        'r' => string.push('\r'),
-                        '\\' => string.push('\\'),
-                        '"' => string.push('"'),
-                        _ => {
-                            string.push('\\');
-                            string.push(escaped);
-                        }
-                    }
-                    self.advance();
+                string.push(self.read_escape()?);
+            } else if ch == '{' && self.peek_next() == Some('{') {
+                self.advance();
+                self.advance();
+                string.push('{');
+            } else if ch == '}' && self.peek_next() == Some('}') {
+                self.advance();
+                self.advance();
+                string.push('}');
+            } else if ch == '{' {
+                if !string.is_empty() {
+                    parts.push(StringPart::Literal(std::mem::take(&mut string)));
                 }
+                parts.push(StringPart::Expression(self.read_interpolation()?));
             } else {
                 string.push(ch);
                 self.advance();
             }
         }
         
-        string
+        if parts.is_empty() {
+            return Ok(TokenType::String(string));
+        }
+        if !string.is_empty() {
+            parts.push(StringPart::Literal(string));
+        }
+        Ok(TokenType::InterpolatedString(parts))
+    }
+    
+    /// Reads the escape sequence after a backslash.
+    fn read_escape(&mut self) -> Result<char, String> {
+        let line = self.line;
+        let escaped = match self.advance() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('x') => {
+                let digits: String = (0..2).filter_map(|_| self.advance()).collect();
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(code) if digits.len() == 2 && code <= 0x7F => code as char,
+                    _ => return Err(format!("Invalid escape '\\x{}' at line {}, expected two hex digits up to 7F", digits, line)),
+                }
+            }
+            Some('u') => {
+                if self.advance() != Some('{') {
+                    return Err(format!("Expected '{{' after '\\u' at line {}", line));
+                }
+                let mut digits = String::new();
+                loop {
+                    match self.advance() {
+                        Some('}') => break,
+                        Some(ch) if ch.is_ascii_hexdigit() && digits.len() < 6 => digits.push(ch),
+                        _ => return Err(format!("Invalid unicode escape at line {}, expected 1 to 6 hex digits and '}}'", line)),
+                    }
+                }
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("Invalid unicode escape '\\u{{{}}}' at line {}", digits, line))?
+            }
+            Some(other) => return Err(format!("Invalid escape sequence '\\{}' at line {}", other, line)),
+            None => return Err(format!("Unterminated string at line {}", line)),
+        };
+        Ok(escaped)
+    }
+    
+    /// Reads a `"""..."""` string. A line break right after the opening quotes and
+    /// the whitespace before the closing quotes are dropped, along with the
+    /// indentation shared by every line.
+    fn read_multiline_string(&mut self) -> Result<TokenType, String> {
+        let line = self.line;
+        for _ in 0..3 {
+            self.advance();
+        }
+        
+        let mut raw = String::new();
+        loop {
+            if self.at_triple_quote() {
+                for _ in 0..3 {
+                    self.advance();
+                }
+                break;
+            }
+            match self.advance() {
+                // keep escapes intact so `\"` cannot end the string
+                Some('\\') => {
+                    raw.push('\\');
+                    if let Some(ch) = self.advance() {
+                        raw.push(ch);
+                    }
+                }
+                Some(ch) => raw.push(ch),
+                None => return Err(format!("Unterminated string starting at line {}", line)),
+            }
+        }
+        
+        let first_line = if raw.starts_with('\n') { line + 1 } else { line };
+        let mut contents = Lexer::new(strip_indentation(&raw));
+        contents.line = first_line;
+        contents.read_string_contents(None, line)
     }
     
-    pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
+    fn at_triple_quote(&self) -> bool {
+        self.input[self.position..].starts_with(&['"', '"', '"'])
+    }
+    
+    /// Whether the input at the current 'r' begins a raw string, `r"..."` or `r#"..."#`.
+    fn at_raw_string(&self) -> bool {
+        let rest = &self.input[self.position + 1..];
+        let hashes = rest.iter().take_while(|&&ch| ch == '#').count();
+        rest.get(hashes) == Some(&'"')
+    }
+    
+    /// Reads a raw string, which has no escapes or interpolation. It ends at a
+    /// quote followed by as many '#' as the opening one.
+    fn read_raw_string(&mut self) -> Result<TokenType, String> {
+        let line = self.line;
+        self.advance(); // Skip 'r'
+        let mut hashes = 0;
+        while self.peek() == Some('#') {
+            self.advance();
+            hashes += 1;
+        }
+        self.advance(); // Skip opening quote
+        
+        let mut string = String::new();
+        loop {
+            match self.advance() {
+                Some('"') if self.input[self.position..].iter().take(hashes).filter(|&&ch| ch == '#').count() == hashes => {
+                    for _ in 0..hashes {
+                        self.advance();
+                    }
+                    break;
+                }
+                Some(ch) => string.push(ch),
+                None => return Err(format!("Unterminated raw string starting at line {}", line)),
+            }
+        }
         
+        Ok(TokenType::String(string))
+    }
+    
+    /// Reads the `{expression}` at the current position of a string literal and
+    /// tokenizes the expression.
+    fn read_interpolation(&mut self) -> Result<Vec<Token>, String> {
         let line = self.line;
+        self.advance(); // Skip '{'
         
-        match self.peek() {
+        let mut code = String::new();
+        let mut depth = 0;
+        loop {
+            match self.advance() {
+                Some('}') if depth == 0 => break,
+                Some(ch @ ('{' | '}')) => {
+                    depth = if ch == '{' { depth + 1 } else { depth - 1 };
+                    code.push(ch);
+                }
+                // copy nested string literals whole so their braces and quotes are not counted
+                Some('"') => {
+                    code.push('"');
+                    while let Some(ch) = self.advance() {
+                        code.push(ch);
+                        if ch == '\\' {
+                            if let Some(escaped) = self.advance() {
+                                code.push(escaped);
+                            }
+                        } else if ch == '"' {
+                            break;
+                        }
+                    }
+                }
+                Some(ch) => code.push(ch),
+                None => return Err(format!("Unterminated interpolation in string at line {}", line)),
+            }
+        }
+        
+        if code.trim().is_empty() {
+            return Err(format!("Empty interpolation in string at line {}", line));
+        }
+        let mut lexer = Lexer::new(code);
+        lexer.line = line;
+        lexer.tokenize()
+    }
+    
+    pub fn next_token(&mut self) -> Result<Token, String> {
+        self.skip_whitespace()?;
+        
+        let line = self.line;
+        
+        let token = match self.peek() {
             None => Token { token_type: TokenType::Eof, line },
             Some(ch) => {
                 match ch {
                     '+' => {
                         self.advance();
-                        Token { token_type: TokenType::Plus, line }
+                        if self.peek() == Some('=') {
+                            self.advance();
+                            Token { token_type: TokenType::PlusAssign, line }
+                        } else {
+                            Token { token_type: TokenType::Plus, line }
+                        }
                     }
                     '-' => {
                         self.advance();
-                        Token { token_type: TokenType::Minus, line }
+                        if self.peek() == Some('=') {
+                            self.advance();
+                            Token { token_type: TokenType::MinusAssign, line }
+                        } else if self.peek() == Some('>') {
+                            self.advance();
+                            Token { token_type: TokenType::Arrow, line }
+                        } else {
+                            Token { token_type: TokenType::Minus, line }
+                        }
                     }
                     '*' => {
                         self.advance();
-                        Token { token_type: TokenType::Multiply, line }
+                        if self.peek() == Some('*') {
+                            self.advance();
+                            Token { token_type: TokenType::Power, line }
+                        } else if self.peek() == Some('=') {
+                            self.advance();
+                            Token { token_type: TokenType::MultiplyAssign, line }
+                        } else {
+                            Token { token_type: TokenType::Multiply, line }
+                        }
+                    }
+                    '%' => {
+                        self.advance();
+                        if self.peek() == Some('=') {
+                            self.advance();
+                            Token { token_type: TokenType::ModuloAssign, line }
+                        } else {
+                            Token { token_type: TokenType::Modulo, line }
+                        }
+                    }
+                    '^' => {
+                        self.advance();
+                        Token { token_type: TokenType::BitXor, line }
+                    }
+                    // other comments were skipped along with whitespace
+                    '/' if self.at_doc_comment() => {
+                        let text = self.read_doc_comment();
+                        Token { token_type: TokenType::DocComment(text), line }
                     }
                     '/' => {
                         self.advance();
-                        // Check for comment
-                        if self.peek() == Some('/') {
-                            // this is a comment, skip it in whitespace handling
-                            // we should never reach here because comments are handled in skip_whitespace
-                            // just in case, i will still handle it because i have nothing else to do
-                            self.advance(); // Skip second '/'
-                            self.skip_line_comment();
-                            self.next_token() // Get the next real token
+                        if self.peek() == Some('=') {
+                            self.advance();
+                            Token { token_type: TokenType::DivideAssign, line }
                         } else {
                             Token { token_type: TokenType::Divide, line }
                         }
@@ -163,6 +442,9 @@ impl Lexer {
                         if self.peek() == Some('=') {
                             self.advance();
                             Token { token_type: TokenType::Equal, line }
+                        } else if self.peek() == Some('>') {
+                            self.advance();
+                            Token { token_type: TokenType::FatArrow, line }
                         } else {
                             Token { token_type: TokenType::Assign, line }
                         }
@@ -173,7 +455,25 @@ impl Lexer {
                             self.advance();
                             Token { token_type: TokenType::NotEqual, line }
                         } else {
-                            panic!("Unexpected character '!' at line {}", line);
+                            Token { token_type: TokenType::Not, line }
+                        }
+                    }
+                    '&' => {
+                        self.advance();
+                        if self.peek() == Some('&') {
+                            self.advance();
+                            Token { token_type: TokenType::And, line }
+                        } else {
+                            Token { token_type: TokenType::BitAnd, line }
+                        }
+                    }
+                    '|' => {
+                        self.advance();
+                        if self.peek() == Some('|') {
+                            self.advance();
+                            Token { token_type: TokenType::Or, line }
+                        } else {
+                            Token { token_type: TokenType::BitOr, line }
                         }
                     }
                     '<' => {
@@ -181,6 +481,9 @@ impl Lexer {
                         if self.peek() == Some('=') {
                             self.advance();
                             Token { token_type: TokenType::LessEqual, line }
+                        } else if self.peek() == Some('<') {
+                            self.advance();
+                            Token { token_type: TokenType::ShiftLeft, line }
                         } else {
                             Token { token_type: TokenType::Less, line }
                         }
@@ -190,6 +493,9 @@ impl Lexer {
                         if self.peek() == Some('=') {
                             self.advance();
                             Token { token_type: TokenType::GreaterEqual, line }
+                        } else if self.peek() == Some('>') {
+                            self.advance();
+                            Token { token_type: TokenType::ShiftRight, line }
                         } else {
                             Token { token_type: TokenType::Greater, line }
                         }
@@ -210,41 +516,127 @@ impl Lexer {
                         self.advance();
                         Token { token_type: TokenType::RightBrace, line }
                     }
+                    '.' => {
+                        self.advance();
+                        if self.peek() == Some('.') {
+                            self.advance();
+                            if self.peek() == Some('=') {
+                                self.advance();
+                                Token { token_type: TokenType::DotDotEqual, line }
+                            } else {
+                                Token { token_type: TokenType::DotDot, line }
+                            }
+                        } else {
+                            Token { token_type: TokenType::Dot, line }
+                        }
+                    }
+                    '?' => {
+                        self.advance();
+                        match self.peek() {
+                            Some('?') => {
+                                self.advance();
+                                Token { token_type: TokenType::QuestionQuestion, line }
+                            }
+                            Some('.') => {
+                                self.advance();
+                                Token { token_type: TokenType::QuestionDot, line }
+                            }
+                            _ => Token { token_type: TokenType::Question, line },
+                        }
+                    }
+                    '[' => {
+                        self.advance();
+                        Token { token_type: TokenType::LeftBracket, line }
+                    }
+                    ']' => {
+                        self.advance();
+                        Token { token_type: TokenType::RightBracket, line }
+                    }
+                    ',' => {
+                        self.advance();
+                        Token { token_type: TokenType::Comma, line }
+                    }
+                    ':' => {
+                        self.advance();
+                        if self.peek() == Some(':') {
+                            self.advance();
+                            Token { token_type: TokenType::ColonColon, line }
+                        } else {
+                            Token { token_type: TokenType::Colon, line }
+                        }
+                    }
                     ';' => {
                         self.advance();
                         Token { token_type: TokenType::Semicolon, line }
                     }
+                    '\'' => {
+                        self.advance();
+                        if self.peek().is_some_and(|ch| ch.is_alphabetic() || ch == '_') {
+                            let label = self.read_identifier();
+                            Token { token_type: TokenType::Label(label), line }
+                        } else {
+                            return Err(format!("Expected label name after '\'' at line {}", line));
+                        }
+                    }
+                    '"' if self.at_triple_quote() => {
+                        let token_type = self.read_multiline_string()?;
+                        Token { token_type, line }
+                    }
                     '"' => {
-                        let string = self.read_string();
-                        Token { token_type: TokenType::String(string), line }
+                        let token_type = self.read_string()?;
+                        Token { token_type, line }
+                    }
+                    'r' if self.at_raw_string() => {
+                        let token_type = self.read_raw_string()?;
+                        Token { token_type, line }
                     }
                     _ if ch.is_ascii_digit() => {
-                        let number = self.read_number();
-                        Token { token_type: TokenType::Number(number), line }
+                        let token_type = self.read_number()?;
+                        Token { token_type, line }
                     }
                     _ if ch.is_alphabetic() || ch == '_' => {
                         let identifier = self.read_identifier();
                         let token_type = match identifier.as_str() {
                             "let" => TokenType::Let,
+                            "const" => TokenType::Const,
                             "if" => TokenType::If,
                             "else" => TokenType::Else,
                             "while" => TokenType::While,
+                            "for" => TokenType::For,
+                            "in" => TokenType::In,
+                            "break" => TokenType::Break,
+                            "continue" => TokenType::Continue,
                             "print" => TokenType::Print,
+                            "fn" => TokenType::Fn,
+                            "return" => TokenType::Return,
+                            "struct" => TokenType::Struct,
+                            "enum" => TokenType::Enum,
+                            "match" => TokenType::Match,
+                            "import" => TokenType::Import,
+                            "throw" => TokenType::Throw,
+                            "try" => TokenType::Try,
+                            "catch" => TokenType::Catch,
+                            "finally" => TokenType::Finally,
+                            "yield" => TokenType::Yield,
+                            "true" => TokenType::True,
+                            "false" => TokenType::False,
+                            "nil" => TokenType::Nil,
                             _ => TokenType::Identifier(identifier),
                         };
                         Token { token_type, line }
                     }
-                    _ => panic!("Unexpected character '{}' at line {}", ch, line),
+                    _ => return Err(format!("Unexpected character '{}' at line {}", ch, line)),
                 }
             }
-        }
+        };
+        Ok(token)
     }
     
-    pub fn tokenize(&mut self) -> Vec<Token> {
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, String> {
         let mut tokens = Vec::new();
         
         loop {
-            let token = self.next_token();
+            let token = self.next_token()?;
             let is_eof = matches!(token.token_type, TokenType::Eof);
             tokens.push(token);
             if is_eof {
@@ -252,10 +644,33 @@ impl Lexer {
             }
         }
         
-        tokens
+        Ok(tokens)
     }
 }
 
+/// Removes the indentation shared by all non-blank lines of a multi-line string,
+/// along with a leading line break and a trailing whitespace-only line.
+fn strip_indentation(raw: &str) -> String {
+    let raw = raw.strip_prefix('\n').unwrap_or(raw);
+    let raw = match raw.rfind('\n') {
+        Some(last) if raw[last + 1..].trim().is_empty() => &raw[..last],
+        None if raw.trim().is_empty() => "",
+        _ => raw,
+    };
+    
+    let indent = raw
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+    
+    raw.lines()
+        .map(|line| line.get(indent..).unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -263,12 +678,12 @@ mod tests {
     #[test]
     fn test_tokenize_simple() {
         let mut lexer = Lexer::new("let x = 42;".to_string());
-        let tokens = lexer.tokenize();
+        let tokens = lexer.tokenize().unwrap();
         
         assert!(matches!(tokens[0].token_type, TokenType::Let));
         assert!(matches!(tokens[1].token_type, TokenType::Identifier(_)));
         assert!(matches!(tokens[2].token_type, TokenType::Assign));
-        assert!(matches!(tokens[3].token_type, TokenType::Number(42.0)));
+        assert!(matches!(tokens[3].token_type, TokenType::Integer(42)));
         assert!(matches!(tokens[4].token_type, TokenType::Semicolon));
         assert!(matches!(tokens[5].token_type, TokenType::Eof));
     }
@@ -276,37 +691,221 @@ mod tests {
     #[test]
     fn test_tokenize_arithmetic() {
         let mut lexer = Lexer::new("3 + 4 * 2".to_string());
-        let tokens = lexer.tokenize();
+        let tokens = lexer.tokenize().unwrap();
         
-        assert!(matches!(tokens[0].token_type, TokenType::Number(3.0)));
+        assert!(matches!(tokens[0].token_type, TokenType::Integer(3)));
         assert!(matches!(tokens[1].token_type, TokenType::Plus));
-        assert!(matches!(tokens[2].token_type, TokenType::Number(4.0)));
+        assert!(matches!(tokens[2].token_type, TokenType::Integer(4)));
         assert!(matches!(tokens[3].token_type, TokenType::Multiply));
-        assert!(matches!(tokens[4].token_type, TokenType::Number(2.0)));
+        assert!(matches!(tokens[4].token_type, TokenType::Integer(2)));
+    }
+    
+    #[test]
+    fn test_integer_and_float_literals() {
+        let mut lexer = Lexer::new("7 7.0 0.5 9223372036854775807".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        
+        assert!(matches!(tokens[0].token_type, TokenType::Integer(7)));
+        assert!(matches!(tokens[1].token_type, TokenType::Float(7.0)));
+        assert!(matches!(tokens[2].token_type, TokenType::Float(0.5)));
+        assert!(matches!(tokens[3].token_type, TokenType::Integer(i64::MAX)));
+    }
+    
+    #[test]
+    fn test_arithmetic_and_bitwise_operators() {
+        let mut lexer = Lexer::new("% ** * & | ^ << >> <= >=".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        
+        assert!(matches!(tokens[0].token_type, TokenType::Modulo));
+        assert!(matches!(tokens[1].token_type, TokenType::Power));
+        assert!(matches!(tokens[2].token_type, TokenType::Multiply));
+        assert!(matches!(tokens[3].token_type, TokenType::BitAnd));
+        assert!(matches!(tokens[4].token_type, TokenType::BitOr));
+        assert!(matches!(tokens[5].token_type, TokenType::BitXor));
+        assert!(matches!(tokens[6].token_type, TokenType::ShiftLeft));
+        assert!(matches!(tokens[7].token_type, TokenType::ShiftRight));
+        assert!(matches!(tokens[8].token_type, TokenType::LessEqual));
+        assert!(matches!(tokens[9].token_type, TokenType::GreaterEqual));
+    }
+    
+    #[test]
+    fn test_compound_assignment_operators() {
+        let mut lexer = Lexer::new("+= -= *= /= %= - =".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        
+        assert!(matches!(tokens[0].token_type, TokenType::PlusAssign));
+        assert!(matches!(tokens[1].token_type, TokenType::MinusAssign));
+        assert!(matches!(tokens[2].token_type, TokenType::MultiplyAssign));
+        assert!(matches!(tokens[3].token_type, TokenType::DivideAssign));
+        assert!(matches!(tokens[4].token_type, TokenType::ModuloAssign));
+        assert!(matches!(tokens[5].token_type, TokenType::Minus));
+        assert!(matches!(tokens[6].token_type, TokenType::Assign));
     }
     
     #[test]
     fn test_comments() {
         let mut lexer = Lexer::new("let x = 5; // This is a comment\nlet y = 10;".to_string());
-        let tokens = lexer.tokenize();
+        let tokens = lexer.tokenize().unwrap();
         
         // Should skip the comment and tokenize normally
         assert!(matches!(tokens[0].token_type, TokenType::Let));
         assert!(matches!(tokens[1].token_type, TokenType::Identifier(_)));
         assert!(matches!(tokens[2].token_type, TokenType::Assign));
-        assert!(matches!(tokens[3].token_type, TokenType::Number(5.0)));
+        assert!(matches!(tokens[3].token_type, TokenType::Integer(5)));
         assert!(matches!(tokens[4].token_type, TokenType::Semicolon));
         assert!(matches!(tokens[5].token_type, TokenType::Let)); // Next line after comment
     }
     
+    #[test]
+    fn test_block_and_doc_comments() {
+        let source = "/* outer /* nested */ still comment */ x\n/// Adds two numbers.\n///   Indented.\n//// not a doc\nfn";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        
+        assert!(matches!(&tokens[0].token_type, TokenType::Identifier(name) if name == "x"));
+        assert_eq!(tokens[1].token_type, TokenType::DocComment("Adds two numbers.".to_string()));
+        assert_eq!(tokens[2].token_type, TokenType::DocComment("  Indented.".to_string()));
+        assert!(matches!(tokens[3].token_type, TokenType::Fn));
+        assert_eq!(tokens[3].line, 5);
+    }
+    
+    #[test]
+    fn test_unterminated_block_comment() {
+        let mut lexer = Lexer::new("let x = 1;\n/* open /* nested */\nlet y = 2;".to_string());
+        
+        assert_eq!(lexer.tokenize().unwrap_err(), "Unterminated block comment starting at line 2");
+    }
+    
     #[test]
     fn test_string_literals() {
         let mut lexer = Lexer::new("\"hello world\"".to_string());
-        let tokens = lexer.tokenize();
+        let tokens = lexer.tokenize().unwrap();
         
         assert!(matches!(tokens[0].token_type, TokenType::String(_)));
         if let TokenType::String(s) = &tokens[0].token_type {
             assert_eq!(s, "hello world");
         }
     }
+    
+    #[test]
+    fn test_string_escapes() {
+        let mut lexer = Lexer::new(r#""\u{1F980} \x41\0\t\"""#.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        
+        assert_eq!(tokens[0].token_type, TokenType::String("🦀 A\0\t\"".to_string()));
+    }
+    
+    #[test]
+    fn test_raw_strings() {
+        let mut lexer = Lexer::new(r###"r"\d+{x}" r#"say "hi""# r"###.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        
+        assert_eq!(tokens[0].token_type, TokenType::String(r"\d+{x}".to_string()));
+        assert_eq!(tokens[1].token_type, TokenType::String(r#"say "hi""#.to_string()));
+        assert!(matches!(&tokens[2].token_type, TokenType::Identifier(name) if name == "r"));
+    }
+    
+    #[test]
+    fn test_multiline_strings() {
+        let source = "let s = \"\"\"\n    first\n      indented \"quoted\"\n\n    last\n    \"\"\";\nx";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        
+        assert_eq!(
+            tokens[3].token_type,
+            TokenType::String("first\n  indented \"quoted\"\n\nlast".to_string())
+        );
+        assert_eq!(tokens[5].line, 7);
+    }
+    
+    #[test]
+    fn test_string_errors() {
+        let cases = [
+            (r#""bad \q""#, r"Invalid escape sequence '\q' at line 1"),
+            (r#""\u{110000}""#, r"Invalid unicode escape '\u{110000}' at line 1"),
+            (r#""\x80""#, r"Invalid escape '\x80' at line 1, expected two hex digits up to 7F"),
+            ("\n\"open", "Unterminated string starting at line 2"),
+            ("r#\"open\"", "Unterminated raw string starting at line 1"),
+            ("\"\"\"open\"\"", "Unterminated string starting at line 1"),
+        ];
+        for (source, expected) in cases {
+            let mut lexer = Lexer::new(source.to_string());
+            assert_eq!(lexer.tokenize().unwrap_err(), expected);
+        }
+    }
+    
+    #[test]
+    fn test_string_interpolation() {
+        let mut lexer = Lexer::new(r#""Hi {name}! {{literal}} {m["k"] + 1}""#.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        
+        let parts = match &tokens[0].token_type {
+            TokenType::InterpolatedString(parts) => parts,
+            other => panic!("expected an interpolated string, got {:?}", other),
+        };
+        assert_eq!(parts.len(), 4);
+        assert!(matches!(&parts[0], StringPart::Literal(s) if s == "Hi "));
+        assert!(matches!(&parts[1], StringPart::Expression(tokens) if tokens.len() == 2));
+        assert!(matches!(&parts[2], StringPart::Literal(s) if s == "! {literal} "));
+        assert!(matches!(&parts[3], StringPart::Expression(tokens) if tokens.len() == 7));
+        assert!(matches!(tokens[1].token_type, TokenType::Eof));
+    }
+    
+    #[test]
+    fn test_range_operators() {
+        let mut lexer = Lexer::new("0..10 1..=2.5".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        
+        assert!(matches!(tokens[0].token_type, TokenType::Integer(0)));
+        assert!(matches!(tokens[1].token_type, TokenType::DotDot));
+        assert!(matches!(tokens[2].token_type, TokenType::Integer(10)));
+        assert!(matches!(tokens[3].token_type, TokenType::Integer(1)));
+        assert!(matches!(tokens[4].token_type, TokenType::DotDotEqual));
+        assert!(matches!(tokens[5].token_type, TokenType::Float(2.5)));
+    }
+    
+    #[test]
+    fn test_labels() {
+        let mut lexer = Lexer::new("'outer: while (true) { break 'outer; }".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        
+        assert!(matches!(&tokens[0].token_type, TokenType::Label(name) if name == "outer"));
+        assert!(matches!(tokens[1].token_type, TokenType::Colon));
+        assert!(matches!(tokens[2].token_type, TokenType::While));
+        assert!(matches!(tokens[7].token_type, TokenType::Break));
+        assert!(matches!(&tokens[8].token_type, TokenType::Label(name) if name == "outer"));
+    }
+    
+    #[test]
+    fn test_enum_and_match_tokens() {
+        let mut lexer = Lexer::new("match s { Shape::Circle(r) => r }".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        
+        assert!(matches!(tokens[0].token_type, TokenType::Match));
+        assert!(matches!(tokens[4].token_type, TokenType::ColonColon));
+        assert!(matches!(tokens[9].token_type, TokenType::FatArrow));
+    }
+    
+    #[test]
+    fn test_nil_aware_operators() {
+        let mut lexer = Lexer::new("a?.b ?? nil".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        
+        assert!(matches!(tokens[1].token_type, TokenType::QuestionDot));
+        assert!(matches!(tokens[3].token_type, TokenType::QuestionQuestion));
+        assert!(matches!(tokens[4].token_type, TokenType::Nil));
+    }
+    
+    #[test]
+    fn test_logical_operators() {
+        let mut lexer = Lexer::new("!true && false || x != y".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        
+        assert!(matches!(tokens[0].token_type, TokenType::Not));
+        assert!(matches!(tokens[1].token_type, TokenType::True));
+        assert!(matches!(tokens[2].token_type, TokenType::And));
+        assert!(matches!(tokens[3].token_type, TokenType::False));
+        assert!(matches!(tokens[4].token_type, TokenType::Or));
+        assert!(matches!(tokens[6].token_type, TokenType::NotEqual));
+    }
 }
\ No newline at end of file
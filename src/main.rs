@@ -4,19 +4,50 @@ mod interpreter;
 mod ast;
 mod token;
 mod value;
+mod environment;
+mod builtins;
+mod modules;
+mod types;
+mod typecheck;
+mod infer;
+mod generator;
+mod resolver;
 
 use lexer::Lexer;
 use parser::Parser;
 use interpreter::Interpreter;
 use std::env;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::thread;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut script = None;
+    // `-I dir` (or `--module-path dir`) adds a directory to search for imported modules
+    let mut search_path = Vec::new();
+    // `--infer` prints the inferred type of every binding instead of running the script
+    let mut infer_only = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-I" | "--module-path" => match args.next() {
+                Some(dir) => search_path.push(PathBuf::from(dir)),
+                None => {
+                    eprintln!("Expected a directory after '{}'", arg);
+                    process::exit(1);
+                }
+            },
+            "--infer" => infer_only = true,
+            _ if script.is_none() => script = Some(arg),
+            _ => {
+                eprintln!("Unexpected argument '{}'", arg);
+                process::exit(1);
+            }
+        }
+    }
     
-    let program = if args.len() > 1 {
-        let filename = &args[1];
+    let program = if let Some(filename) = &script {
         match fs::read_to_string(filename) {
             Ok(content) => content,
             Err(e) => {
@@ -47,20 +78,49 @@ fn main() {
         "#.to_string()
     };
     
-    run_program(program);
+    let script = script.map(PathBuf::from);
+    let runner = thread::Builder::new()
+        .stack_size(interpreter::STACK_SIZE)
+        .spawn(move || run_program(program, script.as_deref(), search_path, infer_only))
+        .unwrap_or_else(|e| {
+            eprintln!("Could not start the interpreter: {}", e);
+            process::exit(1);
+        });
+    if runner.join().is_err() {
+        process::exit(101);
+    }
 }
 
-fn run_program(source: String) {
+fn run_program(source: String, script: Option<&Path>, search_path: Vec<PathBuf>, infer_only: bool) {
     // lexical analysis
     let mut lexer = Lexer::new(source);
-    let tokens = lexer.tokenize();
+    let tokens = match lexer.tokenize() {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            eprintln!("Syntax error: {}", e);
+            return;
+        }
+    };
     
     // parsing
     let mut parser = Parser::new(tokens);
     match parser.parse() {
         Ok(ast) => {
+            if infer_only {
+                report_types(&ast);
+                return;
+            }
+            
+            // type checking
+            if let Err(errors) = typecheck::check(&ast) {
+                for error in errors {
+                    eprintln!("Type error: {}", error);
+                }
+                return;
+            }
+            
             // interpretation
-            let mut interpreter = Interpreter::new();
+            let mut interpreter = Interpreter::with_modules(script, search_path);
             match interpreter.interpret(ast) {
                 Ok(()) => println!("\n🦀 Ferris program executed successfully! 🦀"),
                 Err(e) => eprintln!("Runtime error: {}", e),
@@ -70,6 +130,17 @@ fn run_program(source: String) {
     }
 }
 
+/// Prints the inferred type of every binding, then any contradictions found.
+fn report_types(ast: &[ast::Stmt]) {
+    let inference = infer::infer(ast);
+    for binding in inference.bindings {
+        println!("{}", binding);
+    }
+    for error in inference.errors {
+        eprintln!("Type error: {}", error);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -79,7 +150,7 @@ mod tests {
         let program = "let x = 5; let y = x * 2;".to_string();
         
         let mut lexer = Lexer::new(program);
-        let tokens = lexer.tokenize();
+        let tokens = lexer.tokenize().unwrap();
         let mut parser = Parser::new(tokens);
         let ast = parser.parse().unwrap();
         let mut interpreter = Interpreter::new();
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::value::Value;
+
+const MODULE_EXTENSION: &str = "ferris";
+
+/// Finds module files and remembers which modules have been loaded, so each
+/// one runs only once, and which are still loading, so cycles are caught.
+#[derive(Default)]
+pub struct ModuleLoader {
+    // directories searched after the importing module's own directory
+    search_path: Vec<PathBuf>,
+    // loaded modules by canonical path
+    cache: HashMap<PathBuf, Value>,
+    // modules whose top-level code is running, outermost first
+    loading: Vec<PathBuf>,
+}
+
+impl ModuleLoader {
+    pub fn new(search_path: Vec<PathBuf>) -> Self {
+        Self {
+            search_path,
+            ..Self::default()
+        }
+    }
+    
+    /// Marks the script being run as loading, so importing it back is reported as a cycle.
+    pub fn set_entry_script(&mut self, script: &Path) {
+        if let Ok(script) = script.canonicalize() {
+            self.loading = vec![script];
+        }
+    }
+    
+    /// Finds the file for `import "path";` or `import name;` (which looks for
+    /// `name.ferris`), trying `from_dir` first and then the search path.
+    pub fn resolve(&self, module: &str, from_dir: &Path) -> Result<PathBuf, String> {
+        let relative = if module.ends_with(&format!(".{}", MODULE_EXTENSION)) {
+            PathBuf::from(module)
+        } else {
+            PathBuf::from(format!("{}.{}", module, MODULE_EXTENSION))
+        };
+        
+        std::iter::once(from_dir)
+            .chain(self.search_path.iter().map(PathBuf::as_path))
+            .map(|dir| dir.join(&relative))
+            .find(|candidate| candidate.is_file())
+            .and_then(|found| found.canonicalize().ok())
+            .ok_or_else(|| format!("Module '{}' not found", module))
+    }
+    
+    pub fn cached(&self, path: &Path) -> Option<Value> {
+        self.cache.get(path).cloned()
+    }
+    
+    /// Records that `path` is about to run, failing if it is already running
+    /// further up the import chain.
+    pub fn begin(&mut self, path: &Path) -> Result<(), String> {
+        if let Some(start) = self.loading.iter().position(|loading| loading == path) {
+            let chain: Vec<_> = self.loading[start..]
+                .iter()
+                .chain(std::iter::once(&path.to_path_buf()))
+                .map(|path| display_name(path))
+                .collect();
+            return Err(format!("Circular import: {}", chain.join(" -> ")));
+        }
+        self.loading.push(path.to_path_buf());
+        Ok(())
+    }
+    
+    /// Records that `path` has finished running, caching its module if it loaded.
+    pub fn finish(&mut self, path: &Path, module: Option<Value>) {
+        self.loading.pop();
+        if let Some(module) = module {
+            self.cache.insert(path.to_path_buf(), module);
+        }
+    }
+}
+
+/// The file name of a module, for messages.
+pub fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
@@ -1,30 +1,70 @@
-use crate::token::{Token, TokenType};
-use crate::ast::{Expr, Stmt, BinaryOp, UnaryOp};
+use crate::token::{StringPart, Token, TokenType};
+use crate::ast::{Expr, Stmt, BinaryOp, LogicalOp, MatchArm, Pattern, UnaryOp};
+use crate::types::Type;
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    function_depth: usize,
+    // labels of the loops enclosing the current statement, innermost last
+    loop_labels: Vec<Option<String>>,
+    // false where a '{' after an identifier must start a block, e.g. `for x in xs { ... }`
+    allow_struct_literals: bool,
+    // true inside a block that produces a value, which control flow may not leave
+    in_expression_block: bool,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self {
+            tokens,
+            current: 0,
+            function_depth: 0,
+            loop_labels: Vec::new(),
+            allow_struct_literals: true,
+            in_expression_block: false,
+        }
+    }
+    
+    /// The tokens left to parse and their positions, leaving out `///` comments:
+    /// only `statement` looks at those, and everywhere else they are skipped
+    /// like `//` comments.
+    fn remaining(&self) -> impl Iterator<Item = (usize, &Token)> {
+        self.tokens
+            .iter()
+            .enumerate()
+            .skip(self.current)
+            .filter(|(_, token)| !matches!(token.token_type, TokenType::DocComment(_)))
     }
     
     fn peek(&self) -> &Token {
-        self.tokens.get(self.current).unwrap_or(&Token {
+        self.remaining().next().map_or(&Token {
             token_type: TokenType::Eof,
             line: 0,
-        })
+        }, |(_, token)| token)
+    }
+    
+    fn peek_ahead(&self, offset: usize) -> &TokenType {
+        self.remaining()
+            .nth(offset)
+            .map_or(&TokenType::Eof, |(_, token)| &token.token_type)
     }
     
     fn advance(&mut self) -> &Token {
-        if self.current < self.tokens.len() {
-            self.current += 1;
+        let next = self.remaining().next().map(|(index, _)| index);
+        if let Some(index) = next {
+            self.current = index + 1;
         }
         self.peek()
     }
     
+    /// The line of the token most recently consumed.
+    fn previous_line(&self) -> usize {
+        self.tokens
+            .get(self.current.saturating_sub(1))
+            .map_or(0, |token| token.line)
+    }
+    
     fn match_token(&mut self, token_type: &TokenType) -> bool {
         if std::mem::discriminant(&self.peek().token_type) == std::mem::discriminant(token_type) {
             self.advance();
@@ -50,59 +90,226 @@ impl Parser {
             statements.push(self.statement()?);
         }
         
+        crate::resolver::check_constants(&statements)?;
         Ok(statements)
     }
     
     fn statement(&mut self) -> Result<Stmt, String> {
+        if let Some(TokenType::DocComment(_)) = self.tokens.get(self.current).map(|token| &token.token_type) {
+            return self.documented_statement();
+        }
+        
         match &self.peek().token_type {
-            TokenType::Let => self.let_statement(),
+            TokenType::Let | TokenType::Const => self.let_statement(),
             TokenType::If => self.if_statement(),
-            TokenType::While => self.while_statement(),
+            TokenType::While => self.while_statement(None),
+            TokenType::For => self.for_statement(None),
+            TokenType::Label(_) => self.labeled_statement(),
+            TokenType::Break => self.break_statement(),
+            TokenType::Continue => self.continue_statement(),
             TokenType::Print => self.print_statement(),
+            // `fn(...)` without a name is a function expression
+            TokenType::Fn if matches!(self.peek_ahead(1), TokenType::Identifier(_)) => self.function_declaration(),
+            TokenType::Struct => self.struct_declaration(),
+            TokenType::Enum => self.enum_declaration(),
+            TokenType::Match => self.match_statement(),
+            TokenType::Import => self.import_statement(),
+            TokenType::Throw => self.throw_statement(),
+            TokenType::Try => self.try_statement(),
+            TokenType::Return => self.return_statement(),
+            TokenType::Yield => self.yield_statement(),
             TokenType::LeftBrace => self.block_statement(),
-            TokenType::Identifier(_) => {
-                // Check if it's an assignment
-                let checkpoint = self.current;
-                if let TokenType::Identifier(name) = &self.peek().token_type {
-                    let name = name.clone();
-                    self.advance();
-                    if matches!(self.peek().token_type, TokenType::Assign) {
-                        self.advance();
-                        let value = self.expression()?;
-                        self.consume(TokenType::Semicolon, "Expected ';' after assignment")?;
-                        return Ok(Stmt::Assignment { name, value });
-                    }
+            _ => self.expression_statement(),
+        }
+    }
+    
+    /// Parses one or more `///` lines and the statement after them. The lines
+    /// document the statement if it is a `let`, `const`, `fn`, `struct` or
+    /// `enum` declaration, and are ignored like `//` comments otherwise.
+    fn documented_statement(&mut self) -> Result<Stmt, String> {
+        let mut lines = Vec::new();
+        while let Some(TokenType::DocComment(text)) = self.tokens.get(self.current).map(|token| &token.token_type) {
+            lines.push(text.clone());
+            self.current += 1;
+        }
+        
+        let mut stmt = self.statement()?;
+        if let Stmt::Let { doc, .. } | Stmt::Function { doc, .. } | Stmt::Struct { doc, .. } | Stmt::Enum { doc, .. } = &mut stmt {
+            *doc = Some(lines.join("\n"));
+        }
+        Ok(stmt)
+    }
+    
+    /// Whether the current token starts a statement other than an expression statement.
+    fn at_statement_keyword(&self) -> bool {
+        matches!(
+            self.peek().token_type,
+            TokenType::Let | TokenType::Const | TokenType::If | TokenType::While | TokenType::For | TokenType::Label(_)
+                | TokenType::Break | TokenType::Continue | TokenType::Print | TokenType::Fn
+                | TokenType::Struct | TokenType::Enum | TokenType::Match | TokenType::Import | TokenType::Return
+                | TokenType::Throw | TokenType::Try | TokenType::Yield | TokenType::LeftBrace
+        )
+    }
+    
+    fn expression_statement(&mut self) -> Result<Stmt, String> {
+        let expr = self.expression()?;
+        self.finish_expression_statement(expr)
+    }
+    
+    /// Completes a statement that began with the already-parsed `expr`: an
+    /// assignment to it, or a plain expression statement.
+    fn finish_expression_statement(&mut self, expr: Expr) -> Result<Stmt, String> {
+        // an expression followed by '=' is the target of an assignment
+        if matches!(self.peek().token_type, TokenType::Assign) {
+            let line = self.peek().line;
+            self.advance();
+            let value = self.expression()?;
+            self.consume(TokenType::Semicolon, "Expected ';' after assignment")?;
+            return match expr {
+                Expr::Identifier(name) => Ok(Stmt::Assignment { name, value, line }),
+                Expr::Index { object, index, optional: false } => Ok(Stmt::IndexAssignment {
+                    object: *object,
+                    index: *index,
+                    value,
+                }),
+                Expr::Get { object, name, optional: false } => Ok(Stmt::SetField {
+                    object: *object,
+                    name,
+                    value,
+                }),
+                Expr::Tuple(_) => {
+                    let pattern = Self::assignment_pattern(expr, line)?;
+                    Ok(Stmt::DestructuringAssignment { pattern, value, line })
                 }
-                // not an assignment, revert and parse as expression
-                self.current = checkpoint;
-                let expr = self.expression()?;
-                self.consume(TokenType::Semicolon, "Expected ';' after expression")?;
-                Ok(Stmt::Expression(expr))
-            }
-            _ => {
-                let expr = self.expression()?;
-                self.consume(TokenType::Semicolon, "Expected ';' after expression")?;
-                Ok(Stmt::Expression(expr))
+                _ => Err(format!("Invalid assignment target at line {}", line)),
+            };
+        }
+        
+        if matches!(
+            self.peek().token_type,
+            TokenType::PlusAssign | TokenType::MinusAssign | TokenType::MultiplyAssign | TokenType::DivideAssign | TokenType::ModuloAssign
+        ) {
+            let line = self.peek().line;
+            let operator = match self.peek().token_type {
+                TokenType::PlusAssign => BinaryOp::Add,
+                TokenType::MinusAssign => BinaryOp::Subtract,
+                TokenType::MultiplyAssign => BinaryOp::Multiply,
+                TokenType::DivideAssign => BinaryOp::Divide,
+                TokenType::ModuloAssign => BinaryOp::Modulo,
+                _ => unreachable!(),
+            };
+            self.advance();
+            if !matches!(expr, Expr::Identifier(_) | Expr::Index { optional: false, .. } | Expr::Get { optional: false, .. }) {
+                return Err(format!("Invalid assignment target at line {}", line));
             }
+            let value = self.expression()?;
+            self.consume(TokenType::Semicolon, "Expected ';' after assignment")?;
+            return Ok(Stmt::CompoundAssignment { target: expr, operator, value, line });
+        }
+        
+        self.consume(TokenType::Semicolon, "Expected ';' after expression")?;
+        Ok(Stmt::Expression(expr))
+    }
+    
+    /// The names a tuple expression on the left of `=` assigns to.
+    fn assignment_pattern(target: Expr, line: usize) -> Result<Pattern, String> {
+        match target {
+            Expr::Identifier(name) if name == "_" => Ok(Pattern::Wildcard),
+            Expr::Identifier(name) => Ok(Pattern::Binding(name)),
+            Expr::Tuple(elements) => Ok(Pattern::Tuple(
+                elements
+                    .into_iter()
+                    .map(|element| Self::assignment_pattern(element, line))
+                    .collect::<Result<_, _>>()?,
+            )),
+            _ => Err(format!("Can only destructure into names at line {}", line)),
         }
     }
     
+    /// `let name = value;` or `const NAME = value;`, either optionally with a
+    /// type annotation (`let name: int = value;`).
     fn let_statement(&mut self) -> Result<Stmt, String> {
-        self.consume(TokenType::Let, "Expected 'let'")?;
+        let line = self.peek().line;
+        let constant = matches!(self.peek().token_type, TokenType::Const);
+        let keyword = if constant { "const" } else { "let" };
+        self.advance();
+        
+        if matches!(self.peek().token_type, TokenType::LeftParen) {
+            return self.destructuring_let(constant, line);
+        }
         
         let name = if let TokenType::Identifier(name) = &self.peek().token_type {
             let name = name.clone();
             self.advance();
             name
         } else {
-            return Err(format!("Expected identifier after 'let' at line {}", self.peek().line));
+            return Err(format!("Expected identifier after '{}' at line {}", keyword, self.peek().line));
+        };
+        let type_annotation = if self.match_token(&TokenType::Colon) {
+            Some(self.type_annotation()?)
+        } else {
+            None
+        };
+        
+        // `let x;` binds nil, but a constant or a typed variable needs its value up front
+        let value = if self.match_token(&TokenType::Assign) {
+            self.expression()?
+        } else if constant {
+            return Err(format!("Constant '{}' must be initialized at line {}", name, line));
+        } else if type_annotation.is_some() {
+            return Err(format!("Variable '{}' with a type annotation must be initialized at line {}", name, line));
+        } else {
+            Expr::Nil
         };
+        self.consume(TokenType::Semicolon, &format!("Expected ';' after {} statement", keyword))?;
         
-        self.consume(TokenType::Assign, "Expected '=' after variable name")?;
+        Ok(Stmt::Let { name, type_annotation, value, constant, doc: None, line })
+    }
+    
+    /// The rest of `let (a, b) = value;` after the keyword.
+    fn destructuring_let(&mut self, constant: bool, line: usize) -> Result<Stmt, String> {
+        let keyword = if constant { "const" } else { "let" };
+        let pattern = self.destructuring_pattern()?;
+        if !self.match_token(&TokenType::Assign) {
+            return Err(format!("Destructuring '{}' must be initialized at line {}", keyword, line));
+        }
         let value = self.expression()?;
-        self.consume(TokenType::Semicolon, "Expected ';' after let statement")?;
+        self.consume(TokenType::Semicolon, &format!("Expected ';' after {} statement", keyword))?;
+        
+        Ok(Stmt::Destructure { pattern, value, constant, line })
+    }
+    
+    /// A name, `_`, or a parenthesised tuple of these, for a destructuring `let`.
+    fn destructuring_pattern(&mut self) -> Result<Pattern, String> {
+        match self.peek().token_type.clone() {
+            TokenType::Identifier(name) => {
+                self.advance();
+                Ok(if name == "_" { Pattern::Wildcard } else { Pattern::Binding(name) })
+            }
+            TokenType::LeftParen => self.tuple_pattern(Self::destructuring_pattern),
+            _ => Err(format!("Expected a name or '(' to destructure into at line {}", self.peek().line)),
+        }
+    }
+    
+    /// `(p, q)` of patterns parsed by `element`. As in expressions, a single
+    /// pattern only becomes a tuple with a trailing comma, `(p,)`.
+    fn tuple_pattern(&mut self, element: fn(&mut Self) -> Result<Pattern, String>) -> Result<Pattern, String> {
+        self.consume(TokenType::LeftParen, "Expected '('")?;
+        let mut patterns = Vec::new();
+        let mut trailing_comma = false;
+        while !matches!(self.peek().token_type, TokenType::RightParen) {
+            patterns.push(element(self)?);
+            trailing_comma = self.match_token(&TokenType::Comma);
+            if !trailing_comma {
+                break;
+            }
+        }
+        self.consume(TokenType::RightParen, "Expected ')' after tuple pattern")?;
         
-        Ok(Stmt::Let { name, value })
+        if patterns.len() == 1 && !trailing_comma {
+            return Ok(patterns.remove(0));
+        }
+        Ok(Pattern::Tuple(patterns))
     }
     
     fn if_statement(&mut self) -> Result<Stmt, String> {
@@ -122,15 +329,94 @@ impl Parser {
         Ok(Stmt::If { condition, then_stmt, else_stmt })
     }
     
-    fn while_statement(&mut self) -> Result<Stmt, String> {
+    fn while_statement(&mut self, label: Option<String>) -> Result<Stmt, String> {
         self.consume(TokenType::While, "Expected 'while'")?;
         self.consume(TokenType::LeftParen, "Expected '(' after 'while'")?;
         let condition = self.expression()?;
         self.consume(TokenType::RightParen, "Expected ')' after while condition")?;
         
-        let body = Box::new(self.statement()?);
+        self.loop_labels.push(label.clone());
+        let body = self.statement();
+        self.loop_labels.pop();
+        let body = Box::new(body?);
+        
+        Ok(Stmt::While { condition, body, label })
+    }
+    
+    fn for_statement(&mut self, label: Option<String>) -> Result<Stmt, String> {
+        self.consume(TokenType::For, "Expected 'for'")?;
+        
+        let var = if let TokenType::Identifier(name) = &self.peek().token_type {
+            let name = name.clone();
+            self.advance();
+            name
+        } else {
+            return Err(format!("Expected loop variable after 'for' at line {}", self.peek().line));
+        };
+        
+        self.consume(TokenType::In, "Expected 'in' after loop variable")?;
+        let iterable = self.with_struct_literals(false, Self::expression)?;
+        
+        self.loop_labels.push(label.clone());
+        let body = self.block_statement();
+        self.loop_labels.pop();
+        let body = Box::new(body?);
+        
+        Ok(Stmt::For { var, iterable, body, label })
+    }
+    
+    fn labeled_statement(&mut self) -> Result<Stmt, String> {
+        let line = self.peek().line;
+        let label = match &self.peek().token_type {
+            TokenType::Label(label) => label.clone(),
+            _ => return Err(format!("Expected label at line {}", line)),
+        };
+        self.advance();
+        self.consume(TokenType::Colon, "Expected ':' after label")?;
+        
+        match self.peek().token_type {
+            TokenType::While => self.while_statement(Some(label)),
+            TokenType::For => self.for_statement(Some(label)),
+            _ => Err(format!("Label '{} must be followed by a loop at line {}", label, line)),
+        }
+    }
+    
+    fn break_statement(&mut self) -> Result<Stmt, String> {
+        self.consume(TokenType::Break, "Expected 'break'")?;
+        let label = self.loop_jump_target("break")?;
+        Ok(Stmt::Break(label))
+    }
+    
+    fn continue_statement(&mut self) -> Result<Stmt, String> {
+        self.consume(TokenType::Continue, "Expected 'continue'")?;
+        let label = self.loop_jump_target("continue")?;
+        Ok(Stmt::Continue(label))
+    }
+    
+    /// Parses the optional label and trailing ';' of a `break` or `continue`,
+    /// checking that the statement is inside a matching loop.
+    fn loop_jump_target(&mut self, keyword: &str) -> Result<Option<String>, String> {
+        let line = self.peek().line;
+        if self.loop_labels.is_empty() {
+            if self.in_expression_block {
+                return Err(format!("Cannot use '{}' to leave an expression block at line {}", keyword, line));
+            }
+            return Err(format!("Cannot use '{}' outside of a loop at line {}", keyword, line));
+        }
         
-        Ok(Stmt::While { condition, body })
+        let label = if let TokenType::Label(label) = &self.peek().token_type {
+            let label = label.clone();
+            if !self.loop_labels.contains(&Some(label.clone())) {
+                return Err(format!("Undeclared label '{} at line {}", label, line));
+            }
+            self.advance();
+            Some(label)
+        } else {
+            None
+        };
+        
+        self.consume(TokenType::Semicolon, &format!("Expected ';' after '{}'", keyword))?;
+        Ok(label)
     }
     
     fn print_statement(&mut self) -> Result<Stmt, String> {
@@ -143,143 +429,1048 @@ impl Parser {
         Ok(Stmt::Print(expr))
     }
     
-    fn block_statement(&mut self) -> Result<Stmt, String> {
-        self.consume(TokenType::LeftBrace, "Expected '{'")?;
-        let mut statements = Vec::new();
+    fn function_declaration(&mut self) -> Result<Stmt, String> {
+        self.consume(TokenType::Fn, "Expected 'fn'")?;
         
-        while !matches!(self.peek().token_type, TokenType::RightBrace | TokenType::Eof) {
-            statements.push(self.statement()?);
+        let line = self.peek().line;
+        let name = if let TokenType::Identifier(name) = &self.peek().token_type {
+            let name = name.clone();
+            self.advance();
+            name
+        } else {
+            return Err(format!("Expected function name after 'fn' at line {}", self.peek().line));
+        };
+        
+        self.consume(TokenType::LeftParen, "Expected '(' after function name")?;
+        let (params, param_types) = self.parameters(TokenType::RightParen)?;
+        self.consume(TokenType::RightParen, "Expected ')' after parameters")?;
+        let return_type = self.return_type()?;
+        let body = self.function_body(Self::block)?;
+        
+        Ok(Stmt::Function { name, params, param_types, return_type, body, doc: None, line })
+    }
+    
+    /// Parses comma-separated parameter names, each with an optional `: type`,
+    /// up to (but not including) `closing`.
+    fn parameters(&mut self, closing: TokenType) -> Result<(Vec<String>, Vec<Option<Type>>), String> {
+        let mut params: Vec<String> = Vec::new();
+        let mut param_types = Vec::new();
+        if self.peek().token_type == closing {
+            return Ok((params, param_types));
         }
         
-        self.consume(TokenType::RightBrace, "Expected '}' after block")?;
-        Ok(Stmt::Block(statements))
+        loop {
+            if let TokenType::Identifier(param) = &self.peek().token_type {
+                if params.contains(param) {
+                    return Err(format!("Duplicate parameter '{}' at line {}", param, self.peek().line));
+                }
+                params.push(param.clone());
+                self.advance();
+            } else {
+                return Err(format!("Expected parameter name at line {}", self.peek().line));
+            }
+            param_types.push(if self.match_token(&TokenType::Colon) {
+                Some(self.type_annotation()?)
+            } else {
+                None
+            });
+            if !self.match_token(&TokenType::Comma) {
+                break;
+            }
+        }
+        Ok((params, param_types))
     }
     
-    fn expression(&mut self) -> Result<Expr, String> {
-        self.equality()
+    /// The optional `-> type` after a function's parameter list.
+    fn return_type(&mut self) -> Result<Option<Type>, String> {
+        if self.match_token(&TokenType::Arrow) {
+            Ok(Some(self.type_annotation()?))
+        } else {
+            Ok(None)
+        }
     }
     
-    fn equality(&mut self) -> Result<Expr, String> {
-        let mut expr = self.comparison()?;
+    /// A type name such as `int`, `str` or the name of a struct or enum.
+    fn type_annotation(&mut self) -> Result<Type, String> {
+        let ty = match &self.peek().token_type {
+            TokenType::Identifier(name) => Type::from_name(name),
+            TokenType::Nil => Type::Nil,
+            TokenType::Fn => Type::Fn,
+            _ => return Err(format!("Expected type name at line {}", self.peek().line)),
+        };
+        self.advance();
+        Ok(ty)
+    }
+    
+    /// Runs `parse` for the body of a function, where `return` is allowed and
+    /// loops outside the function cannot be targeted.
+    fn function_body<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        let enclosing_loops = std::mem::take(&mut self.loop_labels);
+        let in_expression_block = std::mem::replace(&mut self.in_expression_block, false);
+        self.function_depth += 1;
+        let body = self.with_struct_literals(true, parse);
+        self.function_depth -= 1;
+        self.in_expression_block = in_expression_block;
+        self.loop_labels = enclosing_loops;
+        body
+    }
+    
+    /// `fn(params) { body }` in expression position.
+    fn function_expression(&mut self) -> Result<Expr, String> {
+        let line = self.peek().line;
+        self.consume(TokenType::Fn, "Expected 'fn'")?;
+        self.consume(TokenType::LeftParen, "Expected '(' after 'fn'")?;
+        let (params, param_types) = self.parameters(TokenType::RightParen)?;
+        self.consume(TokenType::RightParen, "Expected ')' after parameters")?;
+        let return_type = self.return_type()?;
+        let body = self.function_body(Self::block)?;
         
-        while matches!(self.peek().token_type, TokenType::Equal | TokenType::NotEqual) {
-            let operator = match self.peek().token_type {
-                TokenType::Equal => BinaryOp::Equal,
-                TokenType::NotEqual => BinaryOp::NotEqual,
-                _ => unreachable!(),
-            };
-            self.advance();
-            let right = self.comparison()?;
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
-            };
-        }
+        Ok(Expr::Lambda { params, param_types, return_type, body, line })
+    }
+    
+    /// `|params| expression`, whose body returns the value of the expression.
+    fn lambda(&mut self) -> Result<Expr, String> {
+        let line = self.peek().line;
+        let (params, param_types) = if self.match_token(&TokenType::Or) {
+            (Vec::new(), Vec::new())
+        } else {
+            self.consume(TokenType::BitOr, "Expected '|' before lambda parameters")?;
+            let params = self.parameters(TokenType::BitOr)?;
+            self.consume(TokenType::BitOr, "Expected '|' after lambda parameters")?;
+            params
+        };
+        let body = self.function_body(Self::expression)?;
         
-        Ok(expr)
+        Ok(Expr::Lambda {
+            params,
+            param_types,
+            return_type: None,
+            body: vec![Stmt::Return { value: Some(body), line }],
+            line,
+        })
     }
     
-    fn comparison(&mut self) -> Result<Expr, String> {
-        let mut expr = self.term()?;
+    fn struct_declaration(&mut self) -> Result<Stmt, String> {
+        self.consume(TokenType::Struct, "Expected 'struct'")?;
         
-        while matches!(self.peek().token_type, TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual) {
-            let operator = match self.peek().token_type {
-                TokenType::Greater => BinaryOp::Greater,
-                TokenType::GreaterEqual => BinaryOp::GreaterEqual,
-                TokenType::Less => BinaryOp::Less,
-                TokenType::LessEqual => BinaryOp::LessEqual,
-                _ => unreachable!(),
-            };
+        let line = self.peek().line;
+        let name = if let TokenType::Identifier(name) = &self.peek().token_type {
+            let name = name.clone();
             self.advance();
-            let right = self.term()?;
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
-            };
+            name
+        } else {
+            return Err(format!("Expected struct name after 'struct' at line {}", self.peek().line));
+        };
+        
+        self.consume(TokenType::LeftBrace, "Expected '{' after struct name")?;
+        let mut fields = Vec::new();
+        while let TokenType::Identifier(field) = &self.peek().token_type {
+            if fields.contains(field) {
+                return Err(format!("Duplicate field '{}' at line {}", field, self.peek().line));
+            }
+            fields.push(field.clone());
+            self.advance();
+            if !self.match_token(&TokenType::Comma) {
+                break;
+            }
         }
+        self.consume(TokenType::RightBrace, "Expected '}' after struct fields")?;
         
-        Ok(expr)
+        Ok(Stmt::Struct { name, fields, doc: None, line })
     }
     
-    fn term(&mut self) -> Result<Expr, String> {
-        let mut expr = self.factor()?;
+    fn enum_declaration(&mut self) -> Result<Stmt, String> {
+        self.consume(TokenType::Enum, "Expected 'enum'")?;
         
-        while matches!(self.peek().token_type, TokenType::Minus | TokenType::Plus) {
-            let operator = match self.peek().token_type {
-                TokenType::Minus => BinaryOp::Subtract,
-                TokenType::Plus => BinaryOp::Add,
-                _ => unreachable!(),
-            };
+        let line = self.peek().line;
+        let name = if let TokenType::Identifier(name) = &self.peek().token_type {
+            let name = name.clone();
             self.advance();
-            let right = self.factor()?;
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
-            };
+            name
+        } else {
+            return Err(format!("Expected enum name after 'enum' at line {}", self.peek().line));
+        };
+        
+        self.consume(TokenType::LeftBrace, "Expected '{' after enum name")?;
+        let mut variants: Vec<(String, Vec<String>)> = Vec::new();
+        while let TokenType::Identifier(variant) = &self.peek().token_type {
+            let variant = variant.clone();
+            if variants.iter().any(|(existing, _)| *existing == variant) {
+                return Err(format!("Duplicate variant '{}' at line {}", variant, self.peek().line));
+            }
+            self.advance();
+            
+            let mut fields = Vec::new();
+            if self.match_token(&TokenType::LeftParen) {
+                while let TokenType::Identifier(field) = &self.peek().token_type {
+                    fields.push(field.clone());
+                    self.advance();
+                    if !self.match_token(&TokenType::Comma) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::RightParen, "Expected ')' after variant fields")?;
+            }
+            variants.push((variant, fields));
+            
+            if !self.match_token(&TokenType::Comma) {
+                break;
+            }
         }
+        self.consume(TokenType::RightBrace, "Expected '}' after enum variants")?;
         
-        Ok(expr)
+        Ok(Stmt::Enum { name, variants, doc: None, line })
     }
     
-    fn factor(&mut self) -> Result<Expr, String> {
-        let mut expr = self.unary()?;
-        
-        while matches!(self.peek().token_type, TokenType::Divide | TokenType::Multiply) {
-            let operator = match self.peek().token_type {
-                TokenType::Divide => BinaryOp::Divide,
-                TokenType::Multiply => BinaryOp::Multiply,
-                _ => unreachable!(),
-            };
-            self.advance();
-            let right = self.unary()?;
-            expr = Expr::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
-            };
-        }
+    /// A `match` at the start of a statement, whose arms are blocks or expressions
+    /// run for their side effects.
+    fn match_statement(&mut self) -> Result<Stmt, String> {
+        let (subject, arms) = self.match_arms(|parser| {
+            if matches!(parser.peek().token_type, TokenType::LeftBrace) {
+                parser.block_statement()
+            } else {
+                Ok(Stmt::Expression(parser.expression()?))
+            }
+        })?;
+        self.match_token(&TokenType::Semicolon);
         
-        Ok(expr)
+        Ok(Stmt::Match { subject, arms })
     }
     
-    fn unary(&mut self) -> Result<Expr, String> {
-        if matches!(self.peek().token_type, TokenType::Minus) {
-            self.advance();
-            let operand = self.unary()?;
-            Ok(Expr::Unary {
-                operator: UnaryOp::Minus,
-                operand: Box::new(operand),
-            })
+    /// `if` in expression position. Its branches are blocks whose final expression
+    /// gives the value.
+    fn if_expression(&mut self) -> Result<Expr, String> {
+        self.consume(TokenType::If, "Expected 'if'")?;
+        self.consume(TokenType::LeftParen, "Expected '(' after 'if'")?;
+        let condition = self.with_struct_literals(true, Self::expression)?;
+        self.consume(TokenType::RightParen, "Expected ')' after if condition")?;
+        
+        let then_branch = self.block_expression()?;
+        let else_branch = if self.match_token(&TokenType::Else) {
+            if matches!(self.peek().token_type, TokenType::If) {
+                Some(Box::new(self.if_expression()?))
+            } else {
+                Some(Box::new(self.block_expression()?))
+            }
         } else {
-            self.primary()
-        }
+            None
+        };
+        
+        Ok(Expr::Conditional {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch,
+        })
     }
     
-    fn primary(&mut self) -> Result<Expr, String> {
-        match &self.peek().token_type {
-            TokenType::Number(n) => {
-                let n = *n;
-                self.advance();
-                Ok(Expr::Number(n))
+    fn match_expression(&mut self) -> Result<Expr, String> {
+        let (subject, arms) = self.match_arms(|parser| {
+            if matches!(parser.peek().token_type, TokenType::LeftBrace) {
+                parser.block_expression()
+            } else {
+                parser.expression()
             }
-            TokenType::String(s) => {
-                let s = s.clone();
+        })?;
+        
+        Ok(Expr::Match { subject: Box::new(subject), arms })
+    }
+    
+    /// Parses `match subject { pattern (if guard)? => body, ... }`, using `arm_body`
+    /// for each body. The comma after an arm is optional when its body is a block.
+    fn match_arms<B>(
+        &mut self,
+        mut arm_body: impl FnMut(&mut Self) -> Result<B, String>,
+    ) -> Result<(Expr, Vec<MatchArm<B>>), String> {
+        self.consume(TokenType::Match, "Expected 'match'")?;
+        let subject = self.with_struct_literals(false, Self::expression)?;
+        self.consume(TokenType::LeftBrace, "Expected '{' after match subject")?;
+        
+        let arms = self.with_struct_literals(true, |parser| {
+            let mut arms = Vec::new();
+            while !matches!(parser.peek().token_type, TokenType::RightBrace | TokenType::Eof) {
+                let pattern = parser.pattern()?;
+                let guard = if parser.match_token(&TokenType::If) {
+                    Some(parser.expression()?)
+                } else {
+                    None
+                };
+                parser.consume(TokenType::FatArrow, "Expected '=>' after match pattern")?;
+                
+                let is_block = matches!(parser.peek().token_type, TokenType::LeftBrace);
+                let body = arm_body(parser)?;
+                arms.push(MatchArm { pattern, guard, body });
+                
+                if !parser.match_token(&TokenType::Comma) && !is_block {
+                    break;
+                }
+            }
+            Ok::<_, String>(arms)
+        })?;
+        self.consume(TokenType::RightBrace, "Expected '}' after match arms")?;
+        
+        Ok((subject, arms))
+    }
+    
+    fn pattern(&mut self) -> Result<Pattern, String> {
+        let line = self.peek().line;
+        let pattern = match self.peek().token_type.clone() {
+            TokenType::Integer(n) => Pattern::Literal(Expr::Integer(n)),
+            TokenType::Float(n) => Pattern::Literal(Expr::Float(n)),
+            TokenType::String(s) => Pattern::Literal(Expr::String(s)),
+            TokenType::True => Pattern::Literal(Expr::Boolean(true)),
+            TokenType::False => Pattern::Literal(Expr::Boolean(false)),
+            TokenType::Nil => Pattern::Literal(Expr::Nil),
+            TokenType::LeftParen => return self.tuple_pattern(Self::pattern),
+            TokenType::Minus => {
                 self.advance();
-                Ok(Expr::String(s))
+                match self.peek().token_type {
+                    TokenType::Integer(n) => Pattern::Literal(Expr::Integer(-n)),
+                    TokenType::Float(n) => Pattern::Literal(Expr::Float(-n)),
+                    _ => return Err(format!("Expected number after '-' in pattern at line {}", line)),
+                }
             }
             TokenType::Identifier(name) => {
-                let name = name.clone();
                 self.advance();
-                Ok(Expr::Identifier(name))
+                if name == "_" {
+                    return Ok(Pattern::Wildcard);
+                }
+                if !self.match_token(&TokenType::ColonColon) {
+                    return Ok(Pattern::Binding(name));
+                }
+                
+                let variant = self.variant_name()?;
+                let mut fields = Vec::new();
+                if self.match_token(&TokenType::LeftParen) {
+                    while !matches!(self.peek().token_type, TokenType::RightParen) {
+                        fields.push(self.pattern()?);
+                        if !self.match_token(&TokenType::Comma) {
+                            break;
+                        }
+                    }
+                    self.consume(TokenType::RightParen, "Expected ')' after variant patterns")?;
+                }
+                return Ok(Pattern::Variant { enum_name: name, variant, fields });
             }
-            TokenType::LeftParen => {
+            _ => return Err(format!("Expected pattern at line {}", line)),
+        };
+        self.advance();
+        Ok(pattern)
+    }
+    
+    /// `import name;` loads `name.ferris`; `import "dir/name.ferris";` loads that
+    /// path. Either way the module is bound to `name`.
+    fn import_statement(&mut self) -> Result<Stmt, String> {
+        let line = self.peek().line;
+        self.consume(TokenType::Import, "Expected 'import'")?;
+        
+        let (path, name) = match &self.peek().token_type {
+            TokenType::Identifier(name) => (name.clone(), name.clone()),
+            TokenType::String(path) => {
+                let name = std::path::Path::new(path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .filter(|stem| {
+                        stem.starts_with(|ch: char| ch.is_alphabetic() || ch == '_')
+                            && stem.chars().all(|ch| ch.is_alphanumeric() || ch == '_')
+                    })
+                    .ok_or_else(|| format!("Module file name in '{}' is not a valid identifier at line {}", path, line))?;
+                (path.clone(), name)
+            }
+            _ => return Err(format!("Expected module name or path after 'import' at line {}", line)),
+        };
+        self.advance();
+        self.consume(TokenType::Semicolon, "Expected ';' after import")?;
+        
+        Ok(Stmt::Import { path, name, line })
+    }
+    
+    fn throw_statement(&mut self) -> Result<Stmt, String> {
+        self.consume(TokenType::Throw, "Expected 'throw'")?;
+        let value = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expected ';' after thrown value")?;
+        
+        Ok(Stmt::Throw(value))
+    }
+    
+    /// `try { ... } catch (e) { ... } finally { ... }`, where either the `catch`
+    /// or the `finally` clause may be left out.
+    fn try_statement(&mut self) -> Result<Stmt, String> {
+        let line = self.peek().line;
+        self.consume(TokenType::Try, "Expected 'try'")?;
+        let body = self.block()?;
+        
+        let catch = if self.match_token(&TokenType::Catch) {
+            self.consume(TokenType::LeftParen, "Expected '(' after 'catch'")?;
+            let name = match &self.peek().token_type {
+                TokenType::Identifier(name) => name.clone(),
+                _ => return Err(format!("Expected error variable name at line {}", self.peek().line)),
+            };
+            self.advance();
+            self.consume(TokenType::RightParen, "Expected ')' after error variable")?;
+            Some((name, self.block()?))
+        } else {
+            None
+        };
+        
+        let finally = if self.match_token(&TokenType::Finally) {
+            Some(self.block()?)
+        } else {
+            None
+        };
+        
+        if catch.is_none() && finally.is_none() {
+            return Err(format!("Expected 'catch' or 'finally' after 'try' block at line {}", line));
+        }
+        Ok(Stmt::Try { body, catch, finally })
+    }
+    
+    fn return_statement(&mut self) -> Result<Stmt, String> {
+        let line = self.peek().line;
+        self.consume(TokenType::Return, "Expected 'return'")?;
+        if self.in_expression_block {
+            return Err(format!("Cannot use 'return' to leave an expression block at line {}", line));
+        }
+        if self.function_depth == 0 {
+            return Err(format!("Cannot return from top-level code at line {}", line));
+        }
+        
+        let value = if matches!(self.peek().token_type, TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::Semicolon, "Expected ';' after return value")?;
+        
+        Ok(Stmt::Return { value, line })
+    }
+    
+    fn yield_statement(&mut self) -> Result<Stmt, String> {
+        let line = self.peek().line;
+        self.consume(TokenType::Yield, "Expected 'yield'")?;
+        if self.in_expression_block {
+            return Err(format!("Cannot use 'yield' inside an expression block at line {}", line));
+        }
+        if self.function_depth == 0 {
+            return Err(format!("Cannot yield from top-level code at line {}", line));
+        }
+        
+        let value = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expected ';' after yielded value")?;
+        
+        Ok(Stmt::Yield { value, line })
+    }
+    
+    fn block_statement(&mut self) -> Result<Stmt, String> {
+        Ok(Stmt::Block(self.block()?))
+    }
+    
+    /// Parses `{ statements... value }`. Control flow cannot leave the block, since
+    /// evaluating it must produce a value.
+    fn block_expression(&mut self) -> Result<Expr, String> {
+        self.consume(TokenType::LeftBrace, "Expected '{'")?;
+        
+        let enclosing_loops = std::mem::take(&mut self.loop_labels);
+        let in_expression_block = std::mem::replace(&mut self.in_expression_block, true);
+        let contents = self.with_struct_literals(true, Self::block_expression_contents);
+        self.in_expression_block = in_expression_block;
+        self.loop_labels = enclosing_loops;
+        let (statements, value) = contents?;
+        
+        self.consume(TokenType::RightBrace, "Expected '}' after block")?;
+        Ok(Expr::Block {
+            statements,
+            value: value.map(Box::new),
+        })
+    }
+    
+    fn block_expression_contents(&mut self) -> Result<(Vec<Stmt>, Option<Expr>), String> {
+        let mut statements = Vec::new();
+        
+        while !matches!(self.peek().token_type, TokenType::RightBrace | TokenType::Eof) {
+            // here `if` and `match` are expressions, so that they can give the block its value
+            if !matches!(self.peek().token_type, TokenType::If | TokenType::Match) && self.at_statement_keyword() {
+                statements.push(self.statement()?);
+                continue;
+            }
+            
+            let expr = self.expression()?;
+            if matches!(self.peek().token_type, TokenType::RightBrace) {
+                return Ok((statements, Some(expr)));
+            }
+            let ends_with_block = match &expr {
+                Expr::Match { .. } => true,
+                Expr::Conditional { then_branch, .. } => matches!(**then_branch, Expr::Block { .. }),
+                _ => false,
+            };
+            if ends_with_block && !matches!(self.peek().token_type, TokenType::Assign) {
+                self.match_token(&TokenType::Semicolon);
+                statements.push(Stmt::Expression(expr));
+            } else {
+                statements.push(self.finish_expression_statement(expr)?);
+            }
+        }
+        
+        Ok((statements, None))
+    }
+    
+    fn block(&mut self) -> Result<Vec<Stmt>, String> {
+        self.consume(TokenType::LeftBrace, "Expected '{'")?;
+        let mut statements = Vec::new();
+        
+        while !matches!(self.peek().token_type, TokenType::RightBrace | TokenType::Eof) {
+            statements.push(self.statement()?);
+        }
+        
+        self.consume(TokenType::RightBrace, "Expected '}' after block")?;
+        Ok(statements)
+    }
+    
+    fn expression(&mut self) -> Result<Expr, String> {
+        self.conditional()
+    }
+    
+    /// `condition ? then : else`, which is right-associative.
+    fn conditional(&mut self) -> Result<Expr, String> {
+        let condition = self.range()?;
+        
+        if self.match_token(&TokenType::Question) {
+            let then_branch = self.expression()?;
+            self.consume(TokenType::Colon, "Expected ':' in conditional expression")?;
+            let else_branch = self.conditional()?;
+            return Ok(Expr::Conditional {
+                condition: Box::new(condition),
+                then_branch: Box::new(then_branch),
+                else_branch: Some(Box::new(else_branch)),
+            });
+        }
+        
+        Ok(condition)
+    }
+    
+    fn range(&mut self) -> Result<Expr, String> {
+        let start = self.coalesce()?;
+        
+        if !matches!(self.peek().token_type, TokenType::DotDot | TokenType::DotDotEqual) {
+            return Ok(start);
+        }
+        let inclusive = matches!(self.peek().token_type, TokenType::DotDotEqual);
+        self.advance();
+        let end = self.coalesce()?;
+        
+        // `step` is only special directly after a range
+        let step = if matches!(&self.peek().token_type, TokenType::Identifier(name) if name == "step") {
+            self.advance();
+            Some(Box::new(self.coalesce()?))
+        } else {
+            None
+        };
+        
+        Ok(Expr::Range {
+            start: Box::new(start),
+            end: Box::new(end),
+            step,
+            inclusive,
+        })
+    }
+    
+    fn coalesce(&mut self) -> Result<Expr, String> {
+        let mut expr = self.or()?;
+        
+        while self.match_token(&TokenType::QuestionQuestion) {
+            let right = self.or()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator: LogicalOp::Coalesce,
+                right: Box::new(right),
+            };
+        }
+        
+        Ok(expr)
+    }
+    
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        
+        while self.match_token(&TokenType::Or) {
+            let right = self.and()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator: LogicalOp::Or,
+                right: Box::new(right),
+            };
+        }
+        
+        Ok(expr)
+    }
+    
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.equality()?;
+        
+        while self.match_token(&TokenType::And) {
+            let right = self.equality()?;
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator: LogicalOp::And,
+                right: Box::new(right),
+            };
+        }
+        
+        Ok(expr)
+    }
+    
+    fn equality(&mut self) -> Result<Expr, String> {
+        let mut expr = self.comparison()?;
+        
+        while matches!(self.peek().token_type, TokenType::Equal | TokenType::NotEqual) {
+            let operator = match self.peek().token_type {
+                TokenType::Equal => BinaryOp::Equal,
+                TokenType::NotEqual => BinaryOp::NotEqual,
+                _ => unreachable!(),
+            };
+            self.advance();
+            let line = self.previous_line();
+            let right = self.comparison()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+                line,
+            };
+        }
+        
+        Ok(expr)
+    }
+    
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let mut expr = self.bit_or()?;
+        
+        while matches!(self.peek().token_type, TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual) {
+            let operator = match self.peek().token_type {
+                TokenType::Greater => BinaryOp::Greater,
+                TokenType::GreaterEqual => BinaryOp::GreaterEqual,
+                TokenType::Less => BinaryOp::Less,
+                TokenType::LessEqual => BinaryOp::LessEqual,
+                _ => unreachable!(),
+            };
+            self.advance();
+            let line = self.previous_line();
+            let right = self.bit_or()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+                line,
+            };
+        }
+        
+        Ok(expr)
+    }
+    
+    // bitwise operators bind tighter than comparisons, so `x & 1 == 0` means `(x & 1) == 0`
+    fn bit_or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.bit_xor()?;
+        
+        while self.match_token(&TokenType::BitOr) {
+            let line = self.previous_line();
+            let right = self.bit_xor()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator: BinaryOp::BitOr,
+                right: Box::new(right),
+                line,
+            };
+        }
+        
+        Ok(expr)
+    }
+    
+    fn bit_xor(&mut self) -> Result<Expr, String> {
+        let mut expr = self.bit_and()?;
+        
+        while self.match_token(&TokenType::BitXor) {
+            let line = self.previous_line();
+            let right = self.bit_and()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator: BinaryOp::BitXor,
+                right: Box::new(right),
+                line,
+            };
+        }
+        
+        Ok(expr)
+    }
+    
+    fn bit_and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.shift()?;
+        
+        while self.match_token(&TokenType::BitAnd) {
+            let line = self.previous_line();
+            let right = self.shift()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator: BinaryOp::BitAnd,
+                right: Box::new(right),
+                line,
+            };
+        }
+        
+        Ok(expr)
+    }
+    
+    fn shift(&mut self) -> Result<Expr, String> {
+        let mut expr = self.term()?;
+        
+        while matches!(self.peek().token_type, TokenType::ShiftLeft | TokenType::ShiftRight) {
+            let operator = match self.peek().token_type {
+                TokenType::ShiftLeft => BinaryOp::ShiftLeft,
+                TokenType::ShiftRight => BinaryOp::ShiftRight,
+                _ => unreachable!(),
+            };
+            self.advance();
+            let line = self.previous_line();
+            let right = self.term()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+                line,
+            };
+        }
+        
+        Ok(expr)
+    }
+    
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut expr = self.factor()?;
+        
+        while matches!(self.peek().token_type, TokenType::Minus | TokenType::Plus) {
+            let operator = match self.peek().token_type {
+                TokenType::Minus => BinaryOp::Subtract,
+                TokenType::Plus => BinaryOp::Add,
+                _ => unreachable!(),
+            };
+            self.advance();
+            let line = self.previous_line();
+            let right = self.factor()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+                line,
+            };
+        }
+        
+        Ok(expr)
+    }
+    
+    fn factor(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        
+        while matches!(self.peek().token_type, TokenType::Divide | TokenType::Multiply | TokenType::Modulo) {
+            let operator = match self.peek().token_type {
+                TokenType::Divide => BinaryOp::Divide,
+                TokenType::Multiply => BinaryOp::Multiply,
+                TokenType::Modulo => BinaryOp::Modulo,
+                _ => unreachable!(),
+            };
+            self.advance();
+            let line = self.previous_line();
+            let right = self.unary()?;
+            expr = Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+                line,
+            };
+        }
+        
+        Ok(expr)
+    }
+    
+    fn unary(&mut self) -> Result<Expr, String> {
+        if matches!(self.peek().token_type, TokenType::Minus | TokenType::Not) {
+            let line = self.peek().line;
+            let operator = match self.peek().token_type {
+                TokenType::Minus => UnaryOp::Minus,
+                TokenType::Not => UnaryOp::Not,
+                _ => unreachable!(),
+            };
+            self.advance();
+            let operand = self.unary()?;
+            Ok(Expr::Unary {
+                operator,
+                operand: Box::new(operand),
+                line,
+            })
+        } else {
+            self.power()
+        }
+    }
+    
+    // `**` binds tighter than a unary operator on its left, so `-2 ** 2` is `-(2 ** 2)`,
+    // and is right-associative because its right operand is parsed as a unary expression
+    fn power(&mut self) -> Result<Expr, String> {
+        let expr = self.call()?;
+        
+        if self.match_token(&TokenType::Power) {
+            let line = self.previous_line();
+            let right = self.unary()?;
+            return Ok(Expr::Binary {
+                left: Box::new(expr),
+                operator: BinaryOp::Power,
+                right: Box::new(right),
+                line,
+            });
+        }
+        
+        Ok(expr)
+    }
+    
+    fn call(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+        
+        loop {
+            if self.match_token(&TokenType::LeftParen) {
+                let line = self.previous_line();
+                let arguments = self.arguments(TokenType::RightParen)?;
+                self.consume(TokenType::RightParen, "Expected ')' after arguments")?;
+                expr = Expr::Call {
+                    callee: Box::new(expr),
+                    arguments,
+                    line,
+                };
+            } else if self.match_token(&TokenType::LeftBracket) {
+                let index = self.with_struct_literals(true, Self::expression)?;
+                self.consume(TokenType::RightBracket, "Expected ']' after index")?;
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    index: Box::new(index),
+                    optional: false,
+                };
+            } else if self.match_token(&TokenType::Dot) {
+                let name = self.member_name()?;
+                expr = Expr::Get {
+                    object: Box::new(expr),
+                    name,
+                    optional: false,
+                };
+            } else if self.match_token(&TokenType::QuestionDot) {
+                if self.match_token(&TokenType::LeftBracket) {
+                    let index = self.with_struct_literals(true, Self::expression)?;
+                    self.consume(TokenType::RightBracket, "Expected ']' after index")?;
+                    expr = Expr::Index {
+                        object: Box::new(expr),
+                        index: Box::new(index),
+                        optional: true,
+                    };
+                } else {
+                    let name = self.member_name()?;
+                    expr = Expr::Get {
+                        object: Box::new(expr),
+                        name,
+                        optional: true,
+                    };
+                }
+            } else {
+                break;
+            }
+        }
+        
+        Ok(expr)
+    }
+    
+    /// Runs `parse` with struct literals enabled or disabled, restoring the previous setting.
+    fn with_struct_literals<T>(&mut self, allowed: bool, parse: impl FnOnce(&mut Self) -> T) -> T {
+        let previous = std::mem::replace(&mut self.allow_struct_literals, allowed);
+        let result = parse(self);
+        self.allow_struct_literals = previous;
+        result
+    }
+    
+    /// Whether the identifier at the current token begins `Name { field: ... }` or `Name {}`.
+    fn at_struct_literal(&self) -> bool {
+        self.allow_struct_literals
+            && matches!(self.peek_ahead(1), TokenType::LeftBrace)
+            && match self.peek_ahead(2) {
+                TokenType::RightBrace => true,
+                TokenType::Identifier(_) => matches!(self.peek_ahead(3), TokenType::Colon),
+                _ => false,
+            }
+    }
+    
+    fn struct_literal(&mut self, name: String) -> Result<Expr, String> {
+        self.consume(TokenType::LeftBrace, "Expected '{' after struct name")?;
+        let mut fields: Vec<(String, Expr)> = Vec::new();
+        
+        while let TokenType::Identifier(field) = &self.peek().token_type {
+            let field = field.clone();
+            let line = self.peek().line;
+            if fields.iter().any(|(existing, _)| *existing == field) {
+                return Err(format!("Field '{}' specified more than once at line {}", field, line));
+            }
+            self.advance();
+            self.consume(TokenType::Colon, "Expected ':' after field name")?;
+            let value = self.with_struct_literals(true, Self::expression)?;
+            fields.push((field, value));
+            if !self.match_token(&TokenType::Comma) {
+                break;
+            }
+        }
+        
+        self.consume(TokenType::RightBrace, "Expected '}' after struct fields")?;
+        Ok(Expr::StructInit { name, fields })
+    }
+    
+    fn member_name(&mut self) -> Result<String, String> {
+        if let TokenType::Identifier(name) = &self.peek().token_type {
+            let name = name.clone();
+            self.advance();
+            Ok(name)
+        } else {
+            Err(format!("Expected property name after '.' at line {}", self.peek().line))
+        }
+    }
+    
+    fn interpolation(&mut self, parts: Vec<StringPart>) -> Result<Expr, String> {
+        let mut pieces = Vec::with_capacity(parts.len());
+        for part in parts {
+            match part {
+                StringPart::Literal(s) => pieces.push(Expr::String(s)),
+                StringPart::Expression(tokens) => {
+                    let mut parser = Parser::new(tokens);
+                    let expr = parser.expression()?;
+                    if !matches!(parser.peek().token_type, TokenType::Eof) {
+                        return Err(format!("Unexpected token in string interpolation at line {}", parser.peek().line));
+                    }
+                    pieces.push(expr);
+                }
+            }
+        }
+        Ok(Expr::Interpolation(pieces))
+    }
+    
+    fn variant_name(&mut self) -> Result<String, String> {
+        if let TokenType::Identifier(name) = &self.peek().token_type {
+            let name = name.clone();
+            self.advance();
+            Ok(name)
+        } else {
+            Err(format!("Expected variant name after '::' at line {}", self.peek().line))
+        }
+    }
+    
+    /// Parses a comma-separated list of expressions up to (but not including)
+    /// the `closing` token. A trailing comma is allowed.
+    fn arguments(&mut self, closing: TokenType) -> Result<Vec<Expr>, String> {
+        let closing = std::mem::discriminant(&closing);
+        let mut arguments = Vec::new();
+        
+        while std::mem::discriminant(&self.peek().token_type) != closing {
+            arguments.push(self.with_struct_literals(true, Self::expression)?);
+            if !self.match_token(&TokenType::Comma) {
+                break;
+            }
+        }
+        
+        Ok(arguments)
+    }
+    
+    fn primary(&mut self) -> Result<Expr, String> {
+        match &self.peek().token_type {
+            TokenType::Integer(n) => {
+                let n = *n;
+                self.advance();
+                Ok(Expr::Integer(n))
+            }
+            TokenType::Float(n) => {
+                let n = *n;
+                self.advance();
+                Ok(Expr::Float(n))
+            }
+            TokenType::String(s) => {
+                let s = s.clone();
+                self.advance();
+                Ok(Expr::String(s))
+            }
+            TokenType::InterpolatedString(parts) => {
+                let parts = parts.clone();
+                self.advance();
+                self.interpolation(parts)
+            }
+            TokenType::True => {
                 self.advance();
-                let expr = self.expression()?;
-                self.consume(TokenType::RightParen, "Expected ')' after expression")?;
-                Ok(expr)
+                Ok(Expr::Boolean(true))
+            }
+            TokenType::False => {
+                self.advance();
+                Ok(Expr::Boolean(false))
+            }
+            TokenType::Nil => {
+                self.advance();
+                Ok(Expr::Nil)
+            }
+            TokenType::Identifier(name) => {
+                let name = name.clone();
+                let is_struct_literal = self.at_struct_literal();
+                self.advance();
+                if is_struct_literal {
+                    self.struct_literal(name)
+                } else if self.match_token(&TokenType::ColonColon) {
+                    let variant = self.variant_name()?;
+                    let arguments = if self.match_token(&TokenType::LeftParen) {
+                        let arguments = self.arguments(TokenType::RightParen)?;
+                        self.consume(TokenType::RightParen, "Expected ')' after variant values")?;
+                        arguments
+                    } else {
+                        Vec::new()
+                    };
+                    Ok(Expr::EnumVariant { enum_name: name, variant, arguments })
+                } else {
+                    Ok(Expr::Identifier(name))
+                }
+            }
+            TokenType::If => self.if_expression(),
+            TokenType::Match => self.match_expression(),
+            TokenType::Fn => self.function_expression(),
+            TokenType::BitOr | TokenType::Or => self.lambda(),
+            TokenType::LeftBracket => {
+                self.advance();
+                let elements = self.arguments(TokenType::RightBracket)?;
+                self.consume(TokenType::RightBracket, "Expected ']' after list elements")?;
+                Ok(Expr::List(elements))
+            }
+            // in expression position '{' always starts a map; blocks only begin statements
+            TokenType::LeftBrace => {
+                self.advance();
+                let mut entries = Vec::new();
+                while !matches!(self.peek().token_type, TokenType::RightBrace) {
+                    let key = self.with_struct_literals(true, Self::expression)?;
+                    self.consume(TokenType::Colon, "Expected ':' after map key")?;
+                    let value = self.with_struct_literals(true, Self::expression)?;
+                    entries.push((key, value));
+                    if !self.match_token(&TokenType::Comma) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::RightBrace, "Expected '}' after map entries")?;
+                Ok(Expr::Map(entries))
+            }
+            TokenType::LeftParen => {
+                self.advance();
+                if self.match_token(&TokenType::RightParen) {
+                    return Ok(Expr::Tuple(Vec::new()));
+                }
+                let expr = self.with_struct_literals(true, Self::expression)?;
+                // a comma makes a tuple; without one the parentheses only group
+                if !self.match_token(&TokenType::Comma) {
+                    self.consume(TokenType::RightParen, "Expected ')' after expression")?;
+                    return Ok(expr);
+                }
+                let mut elements = vec![expr];
+                elements.extend(self.arguments(TokenType::RightParen)?);
+                self.consume(TokenType::RightParen, "Expected ')' after tuple values")?;
+                Ok(Expr::Tuple(elements))
             }
             _ => Err(format!("Unexpected token at line {}", self.peek().line)),
         }
@@ -294,7 +1485,7 @@ mod tests {
     #[test]
     fn test_parse_let_statement() {
         let mut lexer = Lexer::new("let x = 1 + 2;".to_string());
-        let tokens = lexer.tokenize();
+        let tokens = lexer.tokenize().unwrap();
         let mut parser = Parser::new(tokens);
         let ast = parser.parse().unwrap();
         
@@ -305,11 +1496,476 @@ mod tests {
     #[test]
     fn test_parse_expression() {
         let mut lexer = Lexer::new("3 + 4 * 2;".to_string());
-        let tokens = lexer.tokenize();
+        let tokens = lexer.tokenize().unwrap();
         let mut parser = Parser::new(tokens);
         let ast = parser.parse().unwrap();
         
         assert_eq!(ast.len(), 1);
         assert!(matches!(ast[0], Stmt::Expression(_)));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_parse_function_declaration() {
+        let mut lexer = Lexer::new("fn add(a, b) { return a + b; } add(1, 2);".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert_eq!(ast.len(), 2);
+        assert!(matches!(&ast[0], Stmt::Function { params, body, .. } if params.len() == 2 && body.len() == 1));
+        assert!(matches!(&ast[1], Stmt::Expression(Expr::Call { arguments, .. }) if arguments.len() == 2));
+    }
+    
+    #[test]
+    fn test_parse_logical_precedence() {
+        let mut lexer = Lexer::new("a || b && !c;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        // && binds tighter than ||
+        assert!(matches!(
+            &ast[0],
+            Stmt::Expression(Expr::Logical { operator: LogicalOp::Or, right, .. })
+                if matches!(**right, Expr::Logical { operator: LogicalOp::And, .. })
+        ));
+    }
+    
+    #[test]
+    fn test_parse_index_assignment() {
+        let mut lexer = Lexer::new("let xs = [1, 2, 3]; xs[0] = xs[2];".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(&ast[0], Stmt::Let { value: Expr::List(elements), .. } if elements.len() == 3));
+        assert!(matches!(&ast[1], Stmt::IndexAssignment { value: Expr::Index { .. }, .. }));
+    }
+    
+    #[test]
+    fn test_parse_map_literal() {
+        let mut lexer = Lexer::new("let m = {\"name\": \"ferris\", \"legs\": 10}; { let x = 1; }".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(&ast[0], Stmt::Let { value: Expr::Map(entries), .. } if entries.len() == 2));
+        // a brace at the start of a statement is still a block
+        assert!(matches!(&ast[1], Stmt::Block(_)));
+    }
+    
+    #[test]
+    fn test_parse_power_precedence() {
+        let mut lexer = Lexer::new("-2 ** 3 ** 2;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        // -(2 ** (3 ** 2))
+        let Stmt::Expression(Expr::Unary { operand, .. }) = &ast[0] else {
+            panic!("expected a unary minus at the top, got {:?}", ast[0]);
+        };
+        assert!(matches!(
+            &**operand,
+            Expr::Binary { operator: BinaryOp::Power, right, .. }
+                if matches!(**right, Expr::Binary { operator: BinaryOp::Power, .. })
+        ));
+    }
+    
+    #[test]
+    fn test_parse_for_range() {
+        let mut lexer = Lexer::new("for i in 0..=10 step 2 { print(i); }".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(
+            &ast[0],
+            Stmt::For { iterable: Expr::Range { inclusive: true, step: Some(_), .. }, .. }
+        ));
+    }
+    
+    #[test]
+    fn test_parse_compound_assignment() {
+        let mut lexer = Lexer::new("x += 1; xs[0] %= 2;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(&ast[0], Stmt::CompoundAssignment { target: Expr::Identifier(_), operator: BinaryOp::Add, .. }));
+        assert!(matches!(&ast[1], Stmt::CompoundAssignment { target: Expr::Index { .. }, operator: BinaryOp::Modulo, .. }));
+    }
+    
+    #[test]
+    fn test_parse_nil_aware_access() {
+        let mut lexer = Lexer::new("let x; let y = m?.a?.[0] ?? x;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(&ast[0], Stmt::Let { value: Expr::Nil, .. }));
+        let Stmt::Let { value: Expr::Logical { left, operator: LogicalOp::Coalesce, .. }, .. } = &ast[1] else {
+            panic!("expected a coalescing let, got {:?}", ast[1]);
+        };
+        assert!(matches!(
+            &**left,
+            Expr::Index { object, optional: true, .. } if matches!(**object, Expr::Get { optional: true, .. })
+        ));
+    }
+    
+    #[test]
+    fn test_parse_struct() {
+        let source = "struct Point { x, y } let p = Point { x: 1, y: 2 }; p.x = p.y; for q in points { }";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(&ast[0], Stmt::Struct { fields, .. } if fields.len() == 2));
+        assert!(matches!(&ast[1], Stmt::Let { value: Expr::StructInit { fields, .. }, .. } if fields.len() == 2));
+        assert!(matches!(&ast[2], Stmt::SetField { value: Expr::Get { .. }, .. }));
+        // `points { }` is the loop body, not an empty struct literal
+        assert!(matches!(&ast[3], Stmt::For { iterable: Expr::Identifier(_), .. }));
+    }
+    
+    #[test]
+    fn test_parse_enum_and_match() {
+        let source = "
+            enum Shape { Circle(r), Rect(w, h), Empty }
+            let area = match s { Shape::Circle(r) => r * r, Shape::Rect(w, h) if w > 0 => { w * h } _ => 0 };
+            match n { 0 => { print(0); } -1 => log(n), x => {} }
+        ";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(&ast[0], Stmt::Enum { variants, .. } if variants[1].1.len() == 2));
+        if let Stmt::Let { value: Expr::Match { arms, .. }, .. } = &ast[1] {
+            assert_eq!(arms.len(), 3);
+            assert!(matches!(&arms[0].pattern, Pattern::Variant { fields, .. } if fields.len() == 1));
+            assert!(arms[1].guard.is_some());
+            assert!(matches!(&arms[1].body, Expr::Block { statements, value: Some(_) } if statements.is_empty()));
+            assert!(matches!(arms[2].pattern, Pattern::Wildcard));
+        } else {
+            panic!("expected a match expression");
+        }
+        if let Stmt::Match { arms, .. } = &ast[2] {
+            assert!(matches!(arms[1].pattern, Pattern::Literal(Expr::Integer(-1))));
+            assert!(matches!(&arms[2].pattern, Pattern::Binding(name) if name == "x"));
+        } else {
+            panic!("expected a match statement");
+        }
+    }
+    
+    #[test]
+    fn test_control_flow_cannot_leave_expression_block() {
+        let sources = [
+            "fn f(x) { let y = match x { _ => { return 1; } }; }",
+            "while (true) { let y = match 1 { _ => { break; } }; }",
+        ];
+        for source in sources {
+            let mut lexer = Lexer::new(source.to_string());
+            let tokens = lexer.tokenize().unwrap();
+            let mut parser = Parser::new(tokens);
+            
+            assert!(parser.parse().unwrap_err().contains("expression block"));
+        }
+    }
+    
+    #[test]
+    fn test_parse_conditional_expressions() {
+        let source = "let a = x ? 1 : y ? 2 : 3; let b = if (x) { 1 } else if (y) { 2 } else { 3 }; if (x) { } else { }";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        // the ternary is right-associative
+        let Stmt::Let { value: Expr::Conditional { else_branch: Some(else_branch), .. }, .. } = &ast[0] else {
+            panic!("expected a conditional expression");
+        };
+        assert!(matches!(**else_branch, Expr::Conditional { .. }));
+        let Stmt::Let { value: Expr::Conditional { then_branch, else_branch: Some(else_branch), .. }, .. } = &ast[1] else {
+            panic!("expected an if expression");
+        };
+        assert!(matches!(**then_branch, Expr::Block { value: Some(_), .. }));
+        assert!(matches!(**else_branch, Expr::Conditional { .. }));
+        // at the start of a statement `if` is still a statement
+        assert!(matches!(ast[2], Stmt::If { .. }));
+    }
+    
+    #[test]
+    fn test_parse_lambdas() {
+        let source = "let add = |a, b| a + b; let answer = || 42; let f = fn(x) { return x; };";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(
+            &ast[0],
+            Stmt::Let { value: Expr::Lambda { params, body, .. }, .. }
+                if params.len() == 2 && matches!(body[0], Stmt::Return { value: Some(Expr::Binary { .. }), .. })
+        ));
+        assert!(matches!(&ast[1], Stmt::Let { value: Expr::Lambda { params, .. }, .. } if params.is_empty()));
+        assert!(matches!(&ast[2], Stmt::Let { value: Expr::Lambda { params, .. }, .. } if params.len() == 1));
+    }
+    
+    #[test]
+    fn test_parse_interpolation() {
+        let mut lexer = Lexer::new(r#"print("{a} + {b} = {a + b}");"#.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        if let Stmt::Print(Expr::Interpolation(pieces)) = &ast[0] {
+            assert_eq!(pieces.len(), 5);
+            assert!(matches!(&pieces[1], Expr::String(s) if s == " + "));
+            assert!(matches!(pieces[4], Expr::Binary { .. }));
+        } else {
+            panic!("expected an interpolated print");
+        }
+        
+        let mut lexer = Lexer::new(r#"print("{a b}");"#.to_string());
+        let mut parser = Parser::new(lexer.tokenize().unwrap());
+        assert!(parser.parse().is_err());
+    }
+    
+    #[test]
+    fn test_parse_doc_comments() {
+        let source = "/// The answer.\nlet x = 42;\n/// Adds things.\n/// Two lines.\nfn add(a, b) { return a + b; }\nlet y = 1;";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(&ast[0], Stmt::Let { doc: Some(doc), .. } if doc == "The answer."));
+        assert!(matches!(&ast[1], Stmt::Function { doc: Some(doc), .. } if doc == "Adds things.\nTwo lines."));
+        assert!(matches!(&ast[2], Stmt::Let { doc: None, .. }));
+        
+        // a blank line or `//` comment between the doc and its declaration is allowed
+        for source in ["/// Banner.\n\nfn f() {}", "/// Banner.\n// TODO: more\nfn f() {}"] {
+            let mut lexer = Lexer::new(source.to_string());
+            let mut parser = Parser::new(lexer.tokenize().unwrap());
+            let ast = parser.parse().unwrap();
+            
+            assert!(matches!(&ast[0], Stmt::Function { doc: Some(doc), .. } if doc == "Banner."), "for {:?}", source);
+        }
+        
+        // doc comments that document nothing are ignored like `//` comments
+        for source in [
+            "/// Dangling.\nprint(1);",
+            "/// At the end.",
+            "fn f() {\n    let x = 1;\n    /// Nothing below.\n}",
+            "/// Not one name.\nlet (a, b) = (1, 2);",
+            "let xs = [\n    1,\n    /// Inside a list.\n    2,\n];",
+        ] {
+            let mut lexer = Lexer::new(source.to_string());
+            let mut parser = Parser::new(lexer.tokenize().unwrap());
+            let ast = parser.parse().unwrap_or_else(|e| panic!("{} for {:?}", e, source));
+            
+            assert!(
+                ast.iter().all(|stmt| matches!(stmt,
+                    Stmt::Let { doc: None, .. } | Stmt::Function { doc: None, .. } | Stmt::Expression(_)
+                        | Stmt::Print(_) | Stmt::Destructure { .. }
+                )),
+                "expected no docs for {:?}",
+                source
+            );
+        }
+    }
+    
+    #[test]
+    fn test_invalid_assignment_target() {
+        let mut lexer = Lexer::new("1 + 2 = 3;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        
+        assert!(parser.parse().is_err());
+    }
+    
+    #[test]
+    fn test_parse_labeled_break() {
+        let source = "'outer: for i in 0..3 { while (true) { break 'outer; } continue; }";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(&ast[0], Stmt::For { label: Some(label), .. } if label == "outer"));
+    }
+    
+    #[test]
+    fn test_break_outside_loop() {
+        for source in ["break;", "while (true) { fn f() { continue; } }", "while (true) { break 'missing; }"] {
+            let mut lexer = Lexer::new(source.to_string());
+            let tokens = lexer.tokenize().unwrap();
+            let mut parser = Parser::new(tokens);
+            
+            assert!(parser.parse().is_err(), "expected an error for {}", source);
+        }
+    }
+    
+    #[test]
+    fn test_return_outside_function() {
+        let mut lexer = Lexer::new("return 1;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        
+        assert!(parser.parse().is_err());
+    }
+    
+    #[test]
+    fn test_parse_import() {
+        let mut lexer = Lexer::new("import util; import \"lib/strings.ferris\";".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(&ast[0], Stmt::Import { path, name, .. } if path == "util" && name == "util"));
+        assert!(matches!(&ast[1], Stmt::Import { path, name, .. } if path == "lib/strings.ferris" && name == "strings"));
+    }
+    
+    #[test]
+    fn test_parse_try() {
+        let mut lexer = Lexer::new("try { throw 1; } catch (e) { print(e); }".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(&ast[0], Stmt::Try { catch: Some((name, _)), finally: None, .. } if name == "e"));
+        
+        // a try needs at least one of catch and finally
+        let mut lexer = Lexer::new("try { throw 1; }".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        assert!(parser.parse().is_err());
+    }
+    
+    #[test]
+    fn test_parse_const() {
+        let mut lexer = Lexer::new("const LIMIT = 100; { let LIMIT = 1; LIMIT = 2; }".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(&ast[0], Stmt::Let { name, constant: true, .. } if name == "LIMIT"));
+        
+        for (source, error) in [
+            ("const LIMIT = 100; LIMIT = 5;", "Cannot assign to constant"),
+            ("const LIMIT = 100; fn f() { LIMIT += 1; }", "Cannot assign to constant"),
+            ("const (LIMIT, SIZE) = (1, 2); SIZE = 3;", "Cannot assign to constant"),
+            ("fn f() { LIMIT = 2; } const LIMIT = 1;", "Cannot assign to constant"),
+            ("let g = fn() { LIMIT -= 1; }; const LIMIT = 1;", "Cannot assign to constant"),
+            ("{ fn f() { (LIMIT, x) = (1, 2); } const LIMIT = 1; }", "Cannot assign to constant"),
+            ("const LIMIT = 100; let LIMIT = 5;", "Cannot redeclare constant"),
+            ("const LIMIT = 100; fn LIMIT() {}", "Cannot redeclare constant"),
+            ("const LIMIT = 100; struct LIMIT { x }", "Cannot redeclare constant"),
+            ("const LIMIT;", "Constant 'LIMIT' must be initialized"),
+        ] {
+            let mut lexer = Lexer::new(source.to_string());
+            let tokens = lexer.tokenize().unwrap();
+            let mut parser = Parser::new(tokens);
+            let message = parser.parse().unwrap_err();
+            
+            assert!(message.starts_with(error), "expected '{}...' for {}, got '{}'", error, source, message);
+        }
+        
+        // inner scopes may shadow a constant, and outside a function an
+        // assignment runs before a later constant exists
+        for source in [
+            "let LIMIT = 0; { LIMIT = 2; const LIMIT = 1; }",
+            "fn f() { let LIMIT = 0; LIMIT = 2; } const LIMIT = 1;",
+            "fn f(LIMIT) { LIMIT = 2; } const LIMIT = 1;",
+            "const N = 1; for N in 0..3 { N = 2; }",
+            "const E = 1; try { throw 1; } catch (E) { E = 2; }",
+            "const N = 1; match 5 { N => { N = 2; } }",
+            "const A = 1; let f = fn(A) { A = 2; };",
+        ] {
+            let mut lexer = Lexer::new(source.to_string());
+            let tokens = lexer.tokenize().unwrap();
+            let mut parser = Parser::new(tokens);
+            
+            assert!(parser.parse().is_ok(), "expected {} to parse", source);
+        }
+    }
+    
+    #[test]
+    fn test_parse_type_annotations() {
+        let source = "let x: int = 1; fn f(a: str, b) -> num { return 1; } let g = |n: float| n;";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(&ast[0], Stmt::Let { type_annotation: Some(Type::Int), .. }));
+        assert!(matches!(
+            &ast[1],
+            Stmt::Function { param_types, return_type: Some(Type::Num), .. }
+                if param_types[..] == [Some(Type::Str), None]
+        ));
+        assert!(matches!(
+            &ast[2],
+            Stmt::Let { value: Expr::Lambda { param_types, .. }, .. } if param_types[..] == [Some(Type::Float)]
+        ));
+        
+        // a typed variable needs a value to check
+        let mut lexer = Lexer::new("let x: int;".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        assert!(parser.parse().is_err());
+    }
+    
+    #[test]
+    fn test_parse_yield() {
+        let mut lexer = Lexer::new("fn count() { yield 1; }".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        assert!(matches!(&ast[0], Stmt::Function { body, .. } if matches!(body[0], Stmt::Yield { .. })));
+        
+        for source in ["yield 1;", "fn f() { let x = { yield 1; 2 }; }"] {
+            let mut lexer = Lexer::new(source.to_string());
+            let tokens = lexer.tokenize().unwrap();
+            let mut parser = Parser::new(tokens);
+            
+            assert!(parser.parse().is_err(), "expected an error for {}", source);
+        }
+    }
+    
+    #[test]
+    fn test_parse_tuples_and_destructuring() {
+        let source = "let g = (1 + 2); let t = (1,); let e = (); let (q, (r, _)) = t; (q, r) = (r, q);";
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        
+        // parentheses without a comma only group
+        assert!(matches!(&ast[0], Stmt::Let { value: Expr::Binary { .. }, .. }));
+        assert!(matches!(&ast[1], Stmt::Let { value: Expr::Tuple(values), .. } if values.len() == 1));
+        assert!(matches!(&ast[2], Stmt::Let { value: Expr::Tuple(values), .. } if values.is_empty()));
+        assert!(matches!(
+            &ast[3],
+            Stmt::Destructure { pattern: Pattern::Tuple(fields), constant: false, .. }
+                if matches!(fields[..], [Pattern::Binding(_), Pattern::Tuple(_)])
+        ));
+        assert!(matches!(&ast[4], Stmt::DestructuringAssignment { .. }));
+        
+        for source in [
+            "let (a, b);",
+            "let (a, 1) = (1, 1);",
+            "(a, 1) = (1, 1);",
+            "const (a, b) = (1, 2); a = 3;",
+            "const (a, b) = (1, 2); (b, a) = (a, b);",
+        ] {
+            let mut lexer = Lexer::new(source.to_string());
+            let tokens = lexer.tokenize().unwrap();
+            let mut parser = Parser::new(tokens);
+            
+            assert!(parser.parse().is_err(), "expected an error for {}", source);
+        }
+    }
+}
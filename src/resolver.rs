@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use crate::ast::{Expr, MatchArm, Stmt};
+
+/// Checks the rules for constants before the program runs: a constant cannot
+/// be assigned to, or declared again in its own scope, though inner scopes may
+/// shadow it. The names each scope declares are collected before any of it is
+/// walked, so that a function or closure assigning to a constant declared
+/// after it, as in `fn f() { B = 2; } const B = 1;`, is caught too: its body
+/// only runs when it is called, by which time the constant may exist.
+pub fn check_constants(statements: &[Stmt]) -> Result<(), String> {
+    let mut resolver = Resolver { scopes: Vec::new() };
+    resolver.scoped(&[], false, statements)
+}
+
+struct Scope {
+    // every name declared directly in the scope, and whether its last declaration is a constant
+    declared: HashMap<String, bool>,
+    // the names declared so far, in the order the scope runs, and whether each is a constant
+    seen: HashMap<String, bool>,
+    // whether this holds a function's parameters and body, which run only when it is called
+    function: bool,
+}
+
+struct Resolver {
+    // innermost scope last
+    scopes: Vec<Scope>,
+}
+
+impl Resolver {
+    /// Walks `statements` in a new scope that starts with `names` declared.
+    fn scoped(&mut self, names: &[String], function: bool, statements: &[Stmt]) -> Result<(), String> {
+        self.enter(names, function, statements);
+        let result = statements.iter().try_for_each(|stmt| self.stmt(stmt));
+        self.scopes.pop();
+        result
+    }
+    
+    /// Pushes a scope holding `names` and everything `statements` declare.
+    fn enter(&mut self, names: &[String], function: bool, statements: &[Stmt]) {
+        let mut declared: HashMap<String, bool> = names.iter().map(|name| (name.clone(), false)).collect();
+        for stmt in statements {
+            match stmt {
+                Stmt::Let { name, constant, .. } => {
+                    declared.insert(name.clone(), *constant);
+                }
+                Stmt::Destructure { pattern, constant, .. } => {
+                    let mut names = Vec::new();
+                    pattern.bindings(&mut names);
+                    declared.extend(names.into_iter().map(|name| (name, *constant)));
+                }
+                Stmt::Function { name, .. }
+                | Stmt::Struct { name, .. }
+                | Stmt::Enum { name, .. }
+                | Stmt::Import { name, .. } => {
+                    declared.insert(name.clone(), false);
+                }
+                _ => {}
+            }
+        }
+        
+        self.scopes.push(Scope {
+            declared,
+            seen: names.iter().map(|name| (name.clone(), false)).collect(),
+            function,
+        });
+    }
+    
+    /// Records that `name` is now declared in the innermost scope.
+    fn declare(&mut self, name: &str, constant: bool, line: usize) -> Result<(), String> {
+        let scope = self.scopes.last_mut().expect("declarations are always inside a scope");
+        if scope.seen.get(name) == Some(&true) {
+            return Err(format!("Cannot redeclare constant '{}' at line {}", name, line));
+        }
+        scope.seen.insert(name.to_string(), constant);
+        Ok(())
+    }
+    
+    /// Fails if `name` refers to a constant where the assignment runs. Inside a
+    /// function, the enclosing scopes are searched for everything they declare,
+    /// since they may have finished running by the time the function is called.
+    fn check_assignable(&self, name: &str, line: usize) -> Result<(), String> {
+        let mut deferred = false;
+        for scope in self.scopes.iter().rev() {
+            let declared = if deferred { &scope.declared } else { &scope.seen };
+            if let Some(&constant) = declared.get(name) {
+                return match constant {
+                    true => Err(format!("Cannot assign to constant '{}' at line {}", name, line)),
+                    false => Ok(()),
+                };
+            }
+            deferred |= scope.function;
+        }
+        Ok(())
+    }
+    
+    fn stmt(&mut self, stmt: &Stmt) -> Result<(), String> {
+        match stmt {
+            Stmt::Expression(expr) | Stmt::Print(expr) | Stmt::Throw(expr) => self.expr(expr)?,
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value)?;
+                }
+            }
+            Stmt::Yield { value, .. } => self.expr(value)?,
+            // declared after the value, which still sees any outer binding of the name
+            Stmt::Let { name, value, constant, line, .. } => {
+                self.expr(value)?;
+                self.declare(name, *constant, *line)?;
+            }
+            Stmt::Destructure { pattern, value, constant, line } => {
+                self.expr(value)?;
+                let mut names = Vec::new();
+                pattern.bindings(&mut names);
+                for name in &names {
+                    self.declare(name, *constant, *line)?;
+                }
+            }
+            Stmt::Assignment { name, value, line } => {
+                self.expr(value)?;
+                self.check_assignable(name, *line)?;
+            }
+            Stmt::DestructuringAssignment { pattern, value, line } => {
+                self.expr(value)?;
+                let mut names = Vec::new();
+                pattern.bindings(&mut names);
+                for name in &names {
+                    self.check_assignable(name, *line)?;
+                }
+            }
+            Stmt::CompoundAssignment { target, value, line, .. } => {
+                self.expr(target)?;
+                self.expr(value)?;
+                if let Expr::Identifier(name) = target {
+                    self.check_assignable(name, *line)?;
+                }
+            }
+            Stmt::IndexAssignment { object, index, value } => {
+                self.expr(object)?;
+                self.expr(index)?;
+                self.expr(value)?;
+            }
+            Stmt::SetField { object, value, .. } => {
+                self.expr(object)?;
+                self.expr(value)?;
+            }
+            Stmt::If { condition, then_stmt, else_stmt } => {
+                self.expr(condition)?;
+                self.stmt(then_stmt)?;
+                if let Some(else_stmt) = else_stmt {
+                    self.stmt(else_stmt)?;
+                }
+            }
+            Stmt::While { condition, body, .. } => {
+                self.expr(condition)?;
+                self.stmt(body)?;
+            }
+            Stmt::For { var, iterable, body, .. } => {
+                self.expr(iterable)?;
+                self.scoped(std::slice::from_ref(var), false, std::slice::from_ref(body))?;
+            }
+            Stmt::Break(_) | Stmt::Continue(_) => {}
+            Stmt::Block(statements) => self.scoped(&[], false, statements)?,
+            Stmt::Function { name, params, body, line, .. } => {
+                // declared before the body, which may call the function recursively
+                self.declare(name, false, *line)?;
+                self.scoped(params, true, body)?;
+            }
+            Stmt::Struct { name, line, .. } | Stmt::Enum { name, line, .. } | Stmt::Import { name, line, .. } => {
+                self.declare(name, false, *line)?;
+            }
+            Stmt::Match { subject, arms } => {
+                self.expr(subject)?;
+                self.arms(arms, |resolver, body| resolver.stmt(body))?;
+            }
+            Stmt::Try { body, catch, finally } => {
+                self.scoped(&[], false, body)?;
+                if let Some((name, handler)) = catch {
+                    self.scoped(std::slice::from_ref(name), false, handler)?;
+                }
+                if let Some(finally) = finally {
+                    self.scoped(&[], false, finally)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Walks each arm's guard and body with the names its pattern binds in scope.
+    fn arms<B>(
+        &mut self,
+        arms: &[MatchArm<B>],
+        mut body: impl FnMut(&mut Self, &B) -> Result<(), String>,
+    ) -> Result<(), String> {
+        for arm in arms {
+            let mut names = Vec::new();
+            arm.pattern.bindings(&mut names);
+            self.enter(&names, false, &[]);
+            let result = arm.guard.as_ref().map_or(Ok(()), |guard| self.expr(guard)).and_then(|_| body(self, &arm.body));
+            self.scopes.pop();
+            result?;
+        }
+        Ok(())
+    }
+    
+    fn expr(&mut self, expr: &Expr) -> Result<(), String> {
+        match expr {
+            Expr::Integer(_) | Expr::Float(_) | Expr::String(_) | Expr::Boolean(_) | Expr::Nil | Expr::Identifier(_) => {}
+            Expr::Interpolation(elements) | Expr::List(elements) | Expr::Tuple(elements) => {
+                elements.iter().try_for_each(|element| self.expr(element))?;
+            }
+            Expr::Map(entries) => {
+                for (key, value) in entries {
+                    self.expr(key)?;
+                    self.expr(value)?;
+                }
+            }
+            Expr::Index { object, index, .. } => {
+                self.expr(object)?;
+                self.expr(index)?;
+            }
+            Expr::Get { object, .. } => self.expr(object)?,
+            Expr::StructInit { fields, .. } => {
+                fields.iter().try_for_each(|(_, value)| self.expr(value))?;
+            }
+            Expr::EnumVariant { arguments, .. } => {
+                arguments.iter().try_for_each(|argument| self.expr(argument))?;
+            }
+            Expr::Conditional { condition, then_branch, else_branch } => {
+                self.expr(condition)?;
+                self.expr(then_branch)?;
+                if let Some(else_branch) = else_branch {
+                    self.expr(else_branch)?;
+                }
+            }
+            Expr::Match { subject, arms } => {
+                self.expr(subject)?;
+                self.arms(arms, |resolver, body| resolver.expr(body))?;
+            }
+            Expr::Block { statements, value } => {
+                self.enter(&[], false, statements);
+                let result = statements
+                    .iter()
+                    .try_for_each(|stmt| self.stmt(stmt))
+                    .and_then(|_| value.as_ref().map_or(Ok(()), |value| self.expr(value)));
+                self.scopes.pop();
+                result?;
+            }
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.expr(left)?;
+                self.expr(right)?;
+            }
+            Expr::Unary { operand, .. } => self.expr(operand)?,
+            Expr::Range { start, end, step, .. } => {
+                self.expr(start)?;
+                self.expr(end)?;
+                if let Some(step) = step {
+                    self.expr(step)?;
+                }
+            }
+            Expr::Call { callee, arguments, .. } => {
+                self.expr(callee)?;
+                arguments.iter().try_for_each(|argument| self.expr(argument))?;
+            }
+            Expr::Lambda { params, body, .. } => self.scoped(params, true, body)?,
+        }
+        Ok(())
+    }
+}
@@ -1,17 +1,39 @@
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     // Literals
-    Number(f64),
+    Integer(i64),
+    Float(f64),
     String(String),
+    // a string literal containing `{expression}` segments
+    InterpolatedString(Vec<StringPart>),
     Identifier(String),
+    Label(String),
+    // the text of a `///` line, without the slashes
+    DocComment(String),
     
     // Operators
     Plus,
     Minus,
     Multiply,
     Divide,
+    Modulo,
+    Power,
     Assign,
     
+    // Compound assignment operators
+    PlusAssign,
+    MinusAssign,
+    MultiplyAssign,
+    DivideAssign,
+    ModuloAssign,
+    
+    // Bitwise operators
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    
     // Comparison operators
     Equal,
     NotEqual,
@@ -20,25 +42,76 @@ pub enum TokenType {
     LessEqual,
     GreaterEqual,
     
+    // Logical operators
+    And,
+    Or,
+    Not,
+    
+    // Range operators
+    DotDot,
+    DotDotEqual,
+    
+    // Nil-aware operators
+    QuestionQuestion,
+    QuestionDot,
+    
+    // `c ? a : b`
+    Question,
+    
     // Keywords
     Let,
+    Const,
     If,
     Else,
     While,
+    For,
+    In,
+    Break,
+    Continue,
     Print,
+    Fn,
+    Return,
+    Struct,
+    Enum,
+    Match,
+    Import,
+    Throw,
+    Try,
+    Catch,
+    Finally,
+    Yield,
+    True,
+    False,
+    Nil,
     
     // Punctuation
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
+    Comma,
+    Colon,
+    ColonColon,
+    FatArrow,
+    Arrow,
+    Dot,
     Semicolon,
     
     // Special
     Eof,
 }
 
-#[derive(Debug, Clone)]
+/// A piece of an interpolated string literal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringPart {
+    Literal(String),
+    // the tokens of an embedded expression, ending with `Eof`
+    Expression(Vec<Token>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub token_type: TokenType,
     pub line: usize,
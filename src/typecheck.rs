@@ -0,0 +1,681 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast::{BinaryOp, Expr, LogicalOp, MatchArm, Pattern, Stmt, UnaryOp};
+use crate::types::Type;
+
+/// Checks `statements` against their type annotations before they run,
+/// returning every mismatch found, in line order. Unannotated variables have
+/// type `any`, and an operator is only reported when one of its operands has
+/// an annotated type, so code without annotations fails at runtime as before,
+/// where `try` can catch it.
+pub fn check(statements: &[Stmt]) -> Result<(), Vec<String>> {
+    let mut checker = TypeChecker::new();
+    collect_type_names(statements, &mut checker.type_names);
+    checker.check_block(statements);
+    
+    for (name, line) in std::mem::take(&mut checker.named_types) {
+        if !checker.type_names.contains(&name) {
+            checker.error(line, format!("Unknown type '{}'", name));
+        }
+    }
+    if checker.errors.is_empty() {
+        return Ok(());
+    }
+    checker.errors.sort_by_key(|(line, _)| *line);
+    Err(checker.errors
+        .into_iter()
+        .map(|(line, message)| format!("{} at line {}", message, line))
+        .collect())
+}
+
+/// What the checker knows about a name.
+#[derive(Clone)]
+enum Binding {
+    Value(Type),
+    // declared with `fn`, so calls through the name can be checked
+    Function(Signature),
+}
+
+#[derive(Clone)]
+struct Signature {
+    params: Vec<Type>,
+    returns: Type,
+    // whether any of it was written down, so that calls are worth checking
+    annotated: bool,
+}
+
+struct TypeChecker {
+    // innermost scope last
+    scopes: Vec<HashMap<String, Binding>>,
+    // every struct and enum declared in the program
+    type_names: HashSet<String>,
+    // struct and enum names used in annotations, checked once the whole program is seen
+    named_types: Vec<(String, usize)>,
+    // declared return types of the enclosing functions, innermost last
+    return_types: Vec<Type>,
+    errors: Vec<(usize, String)>,
+}
+
+impl TypeChecker {
+    fn new() -> Self {
+        let builtin = |params: Vec<Type>, returns: Type| Binding::Function(Signature { params, returns, annotated: true });
+        let globals = HashMap::from([
+            ("len".to_string(), builtin(vec![Type::Any], Type::Int)),
+            ("push".to_string(), builtin(vec![Type::List, Type::Any], Type::Int)),
+            ("has".to_string(), builtin(vec![Type::Map, Type::Any], Type::Bool)),
+            ("keys".to_string(), builtin(vec![Type::Map], Type::List)),
+            ("values".to_string(), builtin(vec![Type::Map], Type::List)),
+            ("next".to_string(), builtin(vec![Type::Generator], Type::Any)),
+        ]);
+        Self {
+            scopes: vec![globals],
+            type_names: HashSet::from(["Error".to_string()]),
+            named_types: Vec::new(),
+            return_types: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+    
+    fn error(&mut self, line: usize, message: String) {
+        self.errors.push((line, message));
+    }
+    
+    /// Reports a mismatch unless a value of type `actual` fits where `expected` is declared.
+    fn expect(&mut self, expected: &Type, actual: &Type, line: usize, what: impl FnOnce() -> String) {
+        if !expected.accepts(actual) {
+            let message = format!("Type mismatch for {}: expected {}, found {}", what(), expected, actual);
+            self.error(line, message);
+        }
+    }
+    
+    /// Notes the struct or enum names an annotation refers to.
+    fn annotation(&mut self, ty: &Option<Type>, line: usize) -> Type {
+        match ty {
+            Some(Type::Named(name)) => {
+                self.named_types.push((name.clone(), line));
+                Type::Named(name.clone())
+            }
+            Some(ty) => ty.clone(),
+            None => Type::Any,
+        }
+    }
+    
+    fn define(&mut self, name: &str, binding: Binding) {
+        self.scopes
+            .last_mut()
+            .expect("the global scope is never popped")
+            .insert(name.to_string(), binding);
+    }
+    
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+    
+    /// Runs `check` in a new scope in which `names` are bound to `types`.
+    fn in_scope<T>(&mut self, names: &[String], types: &[Type], check: impl FnOnce(&mut Self) -> T) -> T {
+        let scope = names
+            .iter()
+            .zip(types.iter().cloned().chain(std::iter::repeat(Type::Any)))
+            .map(|(name, ty)| (name.clone(), Binding::Value(ty)))
+            .collect();
+        self.scopes.push(scope);
+        let result = check(self);
+        self.scopes.pop();
+        result
+    }
+    
+    fn check_block(&mut self, statements: &[Stmt]) {
+        for stmt in statements {
+            self.check_stmt(stmt);
+        }
+    }
+    
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression(expr) | Stmt::Print(expr) | Stmt::Throw(expr) => {
+                self.expr_type(expr);
+            }
+            Stmt::Let { name, type_annotation, value, line, .. } => {
+                let actual = self.expr_type(value);
+                let ty = match type_annotation {
+                    Some(_) => {
+                        let expected = self.annotation(type_annotation, *line);
+                        self.expect(&expected, &actual, *line, || format!("variable '{}'", name));
+                        expected
+                    }
+                    None => Type::Any,
+                };
+                self.define(name, Binding::Value(ty));
+            }
+            Stmt::Assignment { name, value, line } => {
+                let actual = self.expr_type(value);
+                match self.lookup(name) {
+                    Some(Binding::Value(expected)) => {
+                        let expected = expected.clone();
+                        self.expect(&expected, &actual, *line, || format!("variable '{}'", name));
+                    }
+                    // the function may be replaced by any value, so stop trusting its signature
+                    Some(Binding::Function(_)) => self.rebind(name),
+                    None => {}
+                }
+            }
+            Stmt::Destructure { pattern, value, line, .. } => {
+                self.check_destructured(pattern, value, *line);
+                let mut names = Vec::new();
+                pattern.bindings(&mut names);
+                for name in &names {
+                    self.define(name, Binding::Value(Type::Any));
+                }
+            }
+            Stmt::DestructuringAssignment { pattern, value, line } => {
+                self.check_destructured(pattern, value, *line);
+                let mut names = Vec::new();
+                pattern.bindings(&mut names);
+                for name in &names {
+                    if let Some(Binding::Function(_)) = self.lookup(name) {
+                        self.rebind(name);
+                    }
+                }
+            }
+            Stmt::CompoundAssignment { target, operator, value, line } => {
+                let current = self.expr_type(target);
+                let rhs = self.expr_type(value);
+                let annotated = self.annotated(target) || self.annotated(value);
+                let result = self.binary_type(&current, operator, &rhs, annotated, *line);
+                if let Expr::Identifier(name) = target {
+                    if let Some(Binding::Value(expected)) = self.lookup(name) {
+                        let expected = expected.clone();
+                        self.expect(&expected, &result, *line, || format!("variable '{}'", name));
+                    }
+                }
+            }
+            Stmt::IndexAssignment { object, index, value } => {
+                self.expr_type(object);
+                self.expr_type(index);
+                self.expr_type(value);
+            }
+            Stmt::SetField { object, value, .. } => {
+                self.expr_type(object);
+                self.expr_type(value);
+            }
+            Stmt::If { condition, then_stmt, else_stmt } => {
+                self.expr_type(condition);
+                self.check_stmt(then_stmt);
+                if let Some(else_stmt) = else_stmt {
+                    self.check_stmt(else_stmt);
+                }
+            }
+            Stmt::While { condition, body, .. } => {
+                self.expr_type(condition);
+                self.check_stmt(body);
+            }
+            Stmt::For { var, iterable, body, .. } => {
+                let item = match self.expr_type(iterable) {
+                    _ if !self.annotated(iterable) => Type::Any,
+                    Type::Range => Type::Int,
+                    Type::Str => Type::Str,
+                    _ => Type::Any,
+                };
+                self.in_scope(std::slice::from_ref(var), &[item], |checker| checker.check_stmt(body));
+            }
+            Stmt::Break(_) | Stmt::Continue(_) => {}
+            Stmt::Block(statements) => {
+                self.in_scope(&[], &[], |checker| checker.check_block(statements));
+            }
+            Stmt::Function { name, params, param_types, return_type, body, line, .. } => {
+                let signature = self.signature(param_types, return_type, body, *line);
+                // defined first so that the body can call the function recursively
+                self.define(name, Binding::Function(signature.clone()));
+                self.check_function(params, &signature, body);
+            }
+            Stmt::Return { value, line } => {
+                let actual = match value {
+                    Some(value) => self.expr_type(value),
+                    None => Type::Nil,
+                };
+                if let Some(expected) = self.return_types.last().cloned() {
+                    self.expect(&expected, &actual, *line, || "return value".to_string());
+                }
+            }
+            Stmt::Yield { value, .. } => {
+                self.expr_type(value);
+            }
+            Stmt::Struct { name, .. } | Stmt::Enum { name, .. } => self.define(name, Binding::Value(Type::Any)),
+            Stmt::Match { subject, arms } => {
+                self.expr_type(subject);
+                self.check_arms(arms, |checker, body| checker.check_stmt(body));
+            }
+            Stmt::Import { name, .. } => self.define(name, Binding::Value(Type::Any)),
+            Stmt::Try { body, catch, finally } => {
+                self.in_scope(&[], &[], |checker| checker.check_block(body));
+                if let Some((name, handler)) = catch {
+                    self.in_scope(std::slice::from_ref(name), &[], |checker| checker.check_block(handler));
+                }
+                if let Some(finally) = finally {
+                    self.in_scope(&[], &[], |checker| checker.check_block(finally));
+                }
+            }
+        }
+    }
+    
+    /// Checks that `value` can be destructured by `pattern`. The types of a
+    /// tuple's values are not tracked, so the names it binds are `any`.
+    fn check_destructured(&mut self, pattern: &Pattern, value: &Expr, line: usize) {
+        let actual = self.expr_type(value);
+        if matches!(pattern, Pattern::Tuple(_)) && self.annotated(value) {
+            self.expect(&Type::Tuple, &actual, line, || "destructured value".to_string());
+        }
+    }
+    
+    /// Replaces what is known about the nearest binding of `name` with `any`.
+    fn rebind(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.iter_mut().rev().find(|scope| scope.contains_key(name)) {
+            scope.insert(name.to_string(), Binding::Value(Type::Any));
+        }
+    }
+    
+    /// The signature a function's annotations declare. Calling a generator
+    /// function gives a generator, so its return type must allow one.
+    fn signature(&mut self, param_types: &[Option<Type>], return_type: &Option<Type>, body: &[Stmt], line: usize) -> Signature {
+        let params = param_types.iter().map(|ty| self.annotation(ty, line)).collect();
+        let mut returns = self.annotation(return_type, line);
+        if body.iter().any(Stmt::contains_yield) {
+            self.expect(&returns, &Type::Generator, line, || "generator function result".to_string());
+            returns = Type::Generator;
+        }
+        let annotated = return_type.is_some() || param_types.iter().any(Option::is_some);
+        Signature { params, returns, annotated }
+    }
+    
+    fn check_function(&mut self, params: &[String], signature: &Signature, body: &[Stmt]) {
+        // `return` only stops a generator, so the value it gives is never seen
+        let returns = if body.iter().any(Stmt::contains_yield) {
+            Type::Any
+        } else {
+            signature.returns.clone()
+        };
+        self.return_types.push(returns);
+        self.in_scope(params, &signature.params, |checker| checker.check_block(body));
+        self.return_types.pop();
+    }
+    
+    /// Checks each arm's guard and body with the arm's bindings in scope,
+    /// returning the type each body produced.
+    fn check_arms<B, T>(&mut self, arms: &[MatchArm<B>], mut check_body: impl FnMut(&mut Self, &B) -> T) -> Vec<T> {
+        arms.iter()
+            .map(|arm| {
+                let mut bindings = Vec::new();
+                arm.pattern.bindings(&mut bindings);
+                self.in_scope(&bindings, &[], |checker| {
+                    if let Some(guard) = &arm.guard {
+                        checker.expr_type(guard);
+                    }
+                    check_body(checker, &arm.body)
+                })
+            })
+            .collect()
+    }
+    
+    fn expr_type(&mut self, expr: &Expr) -> Type {
+        match expr {
+            Expr::Integer(_) => Type::Int,
+            Expr::Float(_) => Type::Float,
+            Expr::String(_) => Type::Str,
+            Expr::Interpolation(pieces) => {
+                for piece in pieces {
+                    self.expr_type(piece);
+                }
+                Type::Str
+            }
+            Expr::Boolean(_) => Type::Bool,
+            Expr::Nil => Type::Nil,
+            Expr::Identifier(name) => match self.lookup(name) {
+                Some(Binding::Value(ty)) => ty.clone(),
+                Some(Binding::Function(_)) => Type::Fn,
+                None => Type::Any,
+            },
+            Expr::List(elements) => {
+                for element in elements {
+                    self.expr_type(element);
+                }
+                Type::List
+            }
+            Expr::Tuple(elements) => {
+                for element in elements {
+                    self.expr_type(element);
+                }
+                Type::Tuple
+            }
+            Expr::Map(entries) => {
+                for (key, value) in entries {
+                    self.expr_type(key);
+                    self.expr_type(value);
+                }
+                Type::Map
+            }
+            Expr::Index { object, index, optional } => {
+                let object = self.expr_type(object);
+                self.expr_type(index);
+                match object {
+                    Type::Str if !optional => Type::Str,
+                    _ => Type::Any,
+                }
+            }
+            Expr::Get { object, .. } => {
+                self.expr_type(object);
+                Type::Any
+            }
+            Expr::StructInit { name, fields } => {
+                for (_, value) in fields {
+                    self.expr_type(value);
+                }
+                Type::Named(name.clone())
+            }
+            Expr::EnumVariant { enum_name, arguments, .. } => {
+                for argument in arguments {
+                    self.expr_type(argument);
+                }
+                Type::Named(enum_name.clone())
+            }
+            Expr::Conditional { condition, then_branch, else_branch } => {
+                self.expr_type(condition);
+                let then_type = self.expr_type(then_branch);
+                match else_branch {
+                    Some(else_branch) => {
+                        let else_type = self.expr_type(else_branch);
+                        join(&then_type, &else_type)
+                    }
+                    None => Type::Any,
+                }
+            }
+            Expr::Match { subject, arms } => {
+                self.expr_type(subject);
+                let types = self.check_arms(arms, |checker, body| checker.expr_type(body));
+                types.iter().skip(1).fold(types.first().cloned().unwrap_or(Type::Any), |joined, ty| join(&joined, ty))
+            }
+            Expr::Block { statements, value } => self.in_scope(&[], &[], |checker| {
+                checker.check_block(statements);
+                match value {
+                    Some(value) => checker.expr_type(value),
+                    None => Type::Nil,
+                }
+            }),
+            Expr::Binary { left: left_expr, operator, right: right_expr, line } => {
+                let left = self.expr_type(left_expr);
+                let right = self.expr_type(right_expr);
+                let annotated = self.annotated(left_expr) || self.annotated(right_expr);
+                self.binary_type(&left, operator, &right, annotated, *line)
+            }
+            Expr::Unary { operator, operand: operand_expr, line } => {
+                let operand = self.expr_type(operand_expr);
+                match operator {
+                    UnaryOp::Not => Type::Bool,
+                    UnaryOp::Minus if operand.is_numeric() || operand == Type::Any => operand,
+                    UnaryOp::Minus => {
+                        if self.annotated(operand_expr) {
+                            self.error(*line, format!("Cannot apply '-' to {}", operand));
+                        }
+                        Type::Any
+                    }
+                }
+            }
+            Expr::Range { start, end, step, .. } => {
+                self.expr_type(start);
+                self.expr_type(end);
+                if let Some(step) = step {
+                    self.expr_type(step);
+                }
+                Type::Range
+            }
+            Expr::Logical { left, operator, right } => {
+                let left = self.expr_type(left);
+                let right = self.expr_type(right);
+                match operator {
+                    LogicalOp::And | LogicalOp::Or => Type::Bool,
+                    LogicalOp::Coalesce if left == Type::Nil => right,
+                    LogicalOp::Coalesce => join(&left, &right),
+                }
+            }
+            Expr::Call { callee, arguments, line } => {
+                let arguments: Vec<Type> = arguments.iter().map(|argument| self.expr_type(argument)).collect();
+                let Expr::Identifier(name) = &**callee else {
+                    self.expr_type(callee);
+                    return Type::Any;
+                };
+                let Some(Binding::Function(signature)) = self.lookup(name).cloned() else {
+                    return Type::Any;
+                };
+                if !signature.annotated {
+                    // nothing was declared, so a wrong number of arguments is left to the runtime
+                    return Type::Any;
+                }
+                if arguments.len() != signature.params.len() {
+                    self.error(*line, format!(
+                        "Function '{}' expected {} arguments but got {}",
+                        name,
+                        signature.params.len(),
+                        arguments.len()
+                    ));
+                }
+                for (i, (expected, actual)) in signature.params.iter().zip(&arguments).enumerate() {
+                    self.expect(expected, actual, *line, || format!("argument {} of '{}'", i + 1, name));
+                }
+                signature.returns
+            }
+            Expr::Lambda { params, param_types, return_type, body, line } => {
+                let signature = self.signature(param_types, return_type, body, *line);
+                self.check_function(params, &signature, body);
+                Type::Fn
+            }
+        }
+    }
+    
+    /// Whether the type `expr_type` gives `expr` rests on an annotation, or on
+    /// the declared signature of a builtin.
+    fn annotated(&self, expr: &Expr) -> bool {
+        match expr {
+            // only annotated bindings have a type other than `any`
+            Expr::Identifier(name) => matches!(self.lookup(name), Some(Binding::Value(ty)) if *ty != Type::Any),
+            Expr::Call { callee, .. } => match &**callee {
+                Expr::Identifier(name) => {
+                    matches!(self.lookup(name), Some(Binding::Function(signature)) if signature.annotated)
+                }
+                _ => false,
+            },
+            Expr::Index { object, .. } => self.annotated(object),
+            Expr::Unary { operand, .. } => self.annotated(operand),
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.annotated(left) || self.annotated(right)
+            }
+            Expr::Conditional { then_branch, else_branch, .. } => {
+                self.annotated(then_branch) || else_branch.as_ref().is_some_and(|branch| self.annotated(branch))
+            }
+            _ => false,
+        }
+    }
+    
+    /// The type of `left op right`, reporting operands that would always fail
+    /// at runtime when one of them is `annotated`.
+    fn binary_type(&mut self, left: &Type, op: &BinaryOp, right: &Type, annotated: bool, line: usize) -> Type {
+        let equality = matches!(op, BinaryOp::Equal | BinaryOp::NotEqual);
+        let comparison = matches!(op, BinaryOp::Less | BinaryOp::Greater | BinaryOp::LessEqual | BinaryOp::GreaterEqual);
+        let bitwise = matches!(
+            op,
+            BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor | BinaryOp::ShiftLeft | BinaryOp::ShiftRight
+        );
+        
+        let result = match (left, right) {
+            (Type::Any, Type::Any) => Some(if equality || comparison {
+                Type::Bool
+            } else if bitwise {
+                Type::Int
+            } else {
+                Type::Any
+            }),
+            // the other operand could be anything, so only fail when no operand would do
+            (known, Type::Any) | (Type::Any, known) => match known {
+                _ if equality => Some(Type::Bool),
+                Type::Float if bitwise => None,
+                Type::Int | Type::Num if bitwise => Some(Type::Int),
+                _ if known.is_numeric() && comparison => Some(Type::Bool),
+                // `+` may also be concatenating a string
+                _ if known.is_numeric() && matches!(op, BinaryOp::Add) => Some(Type::Any),
+                _ if known.is_numeric() => Some(Type::Num),
+                Type::Str if matches!(op, BinaryOp::Add) => Some(Type::Str),
+                _ => None,
+            },
+            (l, r) if l.is_numeric() && r.is_numeric() => {
+                if equality || comparison {
+                    Some(Type::Bool)
+                } else if bitwise {
+                    (*l != Type::Float && *r != Type::Float).then_some(Type::Int)
+                } else if *l == Type::Float || *r == Type::Float {
+                    Some(Type::Float)
+                } else if *l == Type::Int && *r == Type::Int && !matches!(op, BinaryOp::Power) {
+                    Some(Type::Int)
+                } else {
+                    // `**` with a negative exponent gives a float
+                    Some(Type::Num)
+                }
+            }
+            (Type::Str, Type::Str) if matches!(op, BinaryOp::Add) => Some(Type::Str),
+            (Type::Str, n) | (n, Type::Str) if n.is_numeric() && matches!(op, BinaryOp::Add) => Some(Type::Str),
+            (Type::Str, Type::Str) | (Type::Bool, Type::Bool) | (Type::Tuple, Type::Tuple) | (Type::Named(_), Type::Named(_))
+                if equality =>
+            {
+                Some(Type::Bool)
+            }
+            (Type::Nil, _) | (_, Type::Nil) if equality => Some(Type::Bool),
+            _ => None,
+        };
+        
+        result.unwrap_or_else(|| {
+            if annotated {
+                self.error(line, format!("Cannot apply '{}' to {} and {}", op.symbol(), left, right));
+            }
+            Type::Any
+        })
+    }
+}
+
+/// The type of a value that comes from one of two places.
+fn join(a: &Type, b: &Type) -> Type {
+    if a == b {
+        a.clone()
+    } else if a.is_numeric() && b.is_numeric() {
+        Type::Num
+    } else {
+        Type::Any
+    }
+}
+
+/// Adds the name of every struct and enum declared anywhere in `statements`.
+fn collect_type_names(statements: &[Stmt], names: &mut HashSet<String>) {
+    for stmt in statements {
+        match stmt {
+            Stmt::Struct { name, .. } | Stmt::Enum { name, .. } => {
+                names.insert(name.clone());
+            }
+            Stmt::Block(body) | Stmt::Function { body, .. } => collect_type_names(body, names),
+            Stmt::If { then_stmt, else_stmt, .. } => {
+                collect_type_names(std::slice::from_ref(then_stmt), names);
+                if let Some(else_stmt) = else_stmt {
+                    collect_type_names(std::slice::from_ref(else_stmt), names);
+                }
+            }
+            Stmt::While { body, .. } | Stmt::For { body, .. } => collect_type_names(std::slice::from_ref(body), names),
+            Stmt::Try { body, catch, finally } => {
+                collect_type_names(body, names);
+                if let Some((_, handler)) = catch {
+                    collect_type_names(handler, names);
+                }
+                if let Some(finally) = finally {
+                    collect_type_names(finally, names);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    
+    fn check_source(source: &str) -> Result<(), Vec<String>> {
+        let mut lexer = Lexer::new(source.to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+        check(&ast)
+    }
+    
+    #[test]
+    fn test_check_annotations() {
+        let source = "
+            let x: int = 5;
+            x = \"five\";
+            fn greet(name: str) -> str {
+                return 42;
+            }
+            greet(1);
+            let ratio: num = 1 / 2.0;
+            let missing: int = nil;
+            let whole: float = 1;
+        ";
+        
+        assert_eq!(check_source(source).unwrap_err(), vec![
+            "Type mismatch for variable 'x': expected int, found str at line 3",
+            "Type mismatch for return value: expected str, found int at line 5",
+            "Type mismatch for argument 1 of 'greet': expected str, found int at line 7",
+            "Type mismatch for variable 'whole': expected float, found int at line 10",
+        ]);
+    }
+    
+    #[test]
+    fn test_check_operators() {
+        let source = "
+            let s: str = \"a\";
+            let f: float = 1.5;
+            fn flag() -> bool { return true; }
+            let a = s - 1;
+            let b = f & 1;
+            let c = -flag();
+            let d = 1 - (s + 1);
+        ";
+        
+        assert_eq!(check_source(source).unwrap_err(), vec![
+            "Cannot apply '-' to str and int at line 5",
+            "Cannot apply '&' to float and int at line 6",
+            "Cannot apply '-' to bool at line 7",
+            "Cannot apply '-' to int and str at line 8",
+        ]);
+    }
+    
+    #[test]
+    fn test_check_unannotated_code() {
+        // unannotated variables may hold anything, as at runtime
+        let source = "
+            let x = 1;
+            x = \"one\";
+            fn add(a, b) { return a + b; }
+            let s = add(\"a\", \"b\") + add(1, 2);
+            struct Point { x, y }
+            let p: Point = Point { x: 1, y: 2 };
+            const NAME = \"ferris\";
+            try { print(\"a\" - 1); } catch (e) { print(e); }
+            try { print(NAME * true); } catch (e) { print(e); }
+            try { print(-\"a\"); } catch (e) { print(e); }
+            try { let (q, r) = 5; } catch (e) { print(e); }
+            try { add(1); } catch (e) { print(e); }
+        ";
+        
+        // operators that fail on unannotated values are left for the runtime to report
+        assert!(check_source(source).is_ok());
+        assert_eq!(check_source("let p: Pointt = 1;").unwrap_err(), vec![
+            "Type mismatch for variable 'p': expected Pointt, found int at line 1",
+            "Unknown type 'Pointt' at line 1",
+        ]);
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+
+/// A type written in an annotation such as `let x: int`, or worked out by the
+/// type checker for an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    Float,
+    // an int or a float
+    Num,
+    Str,
+    Bool,
+    Nil,
+    List,
+    Map,
+    Tuple,
+    Range,
+    Fn,
+    Generator,
+    // a struct or enum, by name
+    Named(String),
+    // unannotated bindings have this type, so nothing is checked about them
+    Any,
+}
+
+impl Type {
+    /// The type an annotation names. Names that are not built-in types are taken
+    /// to be structs or enums.
+    pub fn from_name(name: &str) -> Type {
+        match name {
+            "int" => Type::Int,
+            "float" => Type::Float,
+            "num" => Type::Num,
+            "str" => Type::Str,
+            "bool" => Type::Bool,
+            "list" => Type::List,
+            "map" => Type::Map,
+            "tuple" => Type::Tuple,
+            "range" => Type::Range,
+            "generator" => Type::Generator,
+            "any" => Type::Any,
+            other => Type::Named(other.to_string()),
+        }
+    }
+    
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Int | Type::Float | Type::Num)
+    }
+    
+    /// Whether a value of type `actual` may be stored where `self` is expected.
+    /// A `num` is accepted where an int or float is expected, since it may be
+    /// either, and `nil` is accepted anywhere, as it is by inference.
+    pub fn accepts(&self, actual: &Type) -> bool {
+        match (self, actual) {
+            (Type::Any, _) | (_, Type::Any) | (_, Type::Nil) => true,
+            (Type::Num, actual) => actual.is_numeric(),
+            (Type::Int | Type::Float, Type::Num) => true,
+            (expected, actual) => expected == actual,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Num => write!(f, "num"),
+            Type::Str => write!(f, "str"),
+            Type::Bool => write!(f, "bool"),
+            Type::Nil => write!(f, "nil"),
+            Type::List => write!(f, "list"),
+            Type::Map => write!(f, "map"),
+            Type::Tuple => write!(f, "tuple"),
+            Type::Range => write!(f, "range"),
+            Type::Fn => write!(f, "fn"),
+            Type::Generator => write!(f, "generator"),
+            Type::Named(name) => write!(f, "{}", name),
+            Type::Any => write!(f, "any"),
+        }
+    }
+}
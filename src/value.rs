@@ -1,18 +1,332 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::fmt;
+use std::rc::Rc;
+use crate::ast::Stmt;
+use crate::environment::Environment;
+use crate::generator::Generator;
+use crate::interpreter::{Interpreter, RuntimeError};
 
 #[derive(Debug, Clone)]
 pub enum Value {
-    Number(f64),
+    Nil,
+    Int(i64),
+    Float(f64),
     String(String),
     Boolean(bool),
+    // lists are shared: copies of a list value refer to the same elements
+    List(Rc<RefCell<Vec<Value>>>),
+    // maps are shared like lists and iterate in key order
+    Map(Rc<RefCell<BTreeMap<MapKey, Value>>>),
+    // tuples are fixed once built, so they can share their values freely
+    Tuple(Rc<[Value]>),
+    Range(Range),
+    Function(Rc<Function>),
+    NativeFunction(NativeFunction),
+    StructDef(Rc<StructDef>),
+    // struct instances are shared like lists and maps
+    Struct(Rc<RefCell<StructInstance>>),
+    EnumDef(Rc<EnumDef>),
+    Enum(Rc<EnumValue>),
+    Module(Rc<Module>),
+    // a call to a generator function, resumed by `for` loops and `next()`
+    Generator(Rc<RefCell<Generator>>),
+}
+
+pub struct Function {
+    // `None` for anonymous functions
+    pub name: Option<String>,
+    pub params: Vec<String>,
+    pub body: Rc<[Stmt]>,
+    // whether the body contains a `yield`, so that calls return a generator
+    pub generator: bool,
+    // the scope the function was declared in
+    pub closure: Rc<RefCell<Environment>>,
+}
+
+impl Function {
+    /// The name used in error messages.
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or("<fn>")
+    }
+}
+
+impl fmt::Debug for Function {
+    // the closure may contain this function, so it is left out
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+pub struct StructDef {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct StructInstance {
+    pub def: Rc<StructDef>,
+    // in the same order as `def.fields`
+    pub values: Vec<Value>,
+}
+
+impl StructInstance {
+    fn field_index(&self, name: &str) -> Result<usize, String> {
+        self.def.fields
+            .iter()
+            .position(|field| field == name)
+            .ok_or_else(|| format!("Struct '{}' has no field '{}'", self.def.name, name))
+    }
+    
+    pub fn get(&self, name: &str) -> Result<Value, String> {
+        Ok(self.values[self.field_index(name)?].clone())
+    }
+    
+    pub fn set(&mut self, name: &str, value: Value) -> Result<(), String> {
+        let index = self.field_index(name)?;
+        self.values[index] = value;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct EnumDef {
+    pub name: String,
+    // each variant with the names of its payload fields
+    pub variants: Vec<(String, Vec<String>)>,
+}
+
+impl EnumDef {
+    pub fn variant_index(&self, name: &str) -> Result<usize, String> {
+        self.variants
+            .iter()
+            .position(|(variant, _)| variant == name)
+            .ok_or_else(|| format!("Enum '{}' has no variant '{}'", self.name, name))
+    }
+}
+
+#[derive(Debug)]
+pub struct EnumValue {
+    pub def: Rc<EnumDef>,
+    pub variant: usize,
+    pub values: Vec<Value>,
+}
+
+/// An imported `.ferris` file. Its top-level bindings are its members.
+pub struct Module {
+    pub name: String,
+    pub scope: Rc<RefCell<Environment>>,
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Module")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Module {
+    pub fn get(&self, name: &str) -> Result<Value, String> {
+        self.scope
+            .borrow()
+            .get_local(name)
+            .ok_or_else(|| format!("Module '{}' has no member '{}'", self.name, name))
+    }
+}
+
+/// A range of integers produced by `start..end` or `start..=end`. Ranges are
+/// never materialised; `for` loops step through them one value at a time.
+#[derive(Debug, Clone, Copy)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,
+    pub inclusive: bool,
+}
+
+impl Range {
+    /// Whether `n` has not yet stepped past the end of the range.
+    pub fn is_before_end(&self, n: i64) -> bool {
+        match (self.step > 0, self.inclusive) {
+            (true, true) => n <= self.end,
+            (true, false) => n < self.end,
+            (false, true) => n >= self.end,
+            (false, false) => n > self.end,
+        }
+    }
+}
+
+/// The subset of values that can be used as map keys.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MapKey {
+    Boolean(bool),
+    Integer(i64),
+    String(String),
+}
+
+impl MapKey {
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::Boolean(b) => Ok(MapKey::Boolean(*b)),
+            Value::Int(n) => Ok(MapKey::Integer(*n)),
+            Value::String(s) => Ok(MapKey::String(s.clone())),
+            other => Err(format!("Map keys must be strings, integers or booleans, got '{}'", other)),
+        }
+    }
+    
+    pub fn to_value(&self) -> Value {
+        match self {
+            MapKey::Boolean(b) => Value::Boolean(*b),
+            MapKey::Integer(n) => Value::Int(*n),
+            MapKey::String(s) => Value::String(s.clone()),
+        }
+    }
+}
+
+/// A function implemented in Rust and exposed to Ferris programs.
+#[derive(Debug, Clone, Copy)]
+pub struct NativeFunction {
+    pub name: &'static str,
+    pub arity: usize,
+    pub function: NativeFn,
+}
+
+/// The Rust side of a native function.
+#[derive(Debug, Clone, Copy)]
+pub enum NativeFn {
+    /// Works from its arguments alone.
+    Plain(fn(Vec<Value>) -> Result<Value, String>),
+    /// Needs the interpreter, for instance to run Ferris code.
+    WithInterpreter(fn(&mut Interpreter, Vec<Value>) -> Result<Value, RuntimeError>),
+}
+
+impl Value {
+    /// Formats the value as it appears inside a collection, where strings are quoted.
+    pub fn to_nested_string(&self) -> String {
+        match self {
+            Value::String(s) => format!("{:?}", s),
+            other => other.to_string(),
+        }
+    }
+}
+
+thread_local! {
+    // the lists, maps and structs being displayed, innermost last
+    static DISPLAYING: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Writes a shared collection with `display`, or `placeholder` if it is already
+/// being displayed further out, so one that contains itself prints like
+/// `[1, [...]]` instead of recursing forever.
+fn display_once<T>(
+    f: &mut fmt::Formatter<'_>,
+    shared: &Rc<T>,
+    placeholder: &str,
+    display: impl FnOnce(&mut fmt::Formatter<'_>) -> fmt::Result,
+) -> fmt::Result {
+    let pointer = Rc::as_ptr(shared) as *const ();
+    if DISPLAYING.with(|displaying| displaying.borrow().contains(&pointer)) {
+        return write!(f, "{}", placeholder);
+    }
+    DISPLAYING.with(|displaying| displaying.borrow_mut().push(pointer));
+    let result = display(f);
+    DISPLAYING.with(|displaying| displaying.borrow_mut().pop());
+    result
 }
 
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Value::Number(n) => write!(f, "{}", n),
+            Value::Nil => write!(f, "nil"),
+            Value::Int(n) => write!(f, "{}", n),
+            // keep the decimal point so floats are distinguishable from ints
+            Value::Float(n) if n.is_finite() && n.fract() == 0.0 => write!(f, "{:.1}", n),
+            Value::Float(n) => write!(f, "{}", n),
             Value::String(s) => write!(f, "{}", s),
             Value::Boolean(b) => write!(f, "{}", b),
+            Value::List(elements) => display_once(f, elements, "[...]", |f| {
+                write!(f, "[")?;
+                for (i, element) in elements.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element.to_nested_string())?;
+                }
+                write!(f, "]")
+            }),
+            Value::Map(entries) => display_once(f, entries, "{...}", |f| {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key.to_value().to_nested_string(), value.to_nested_string())?;
+                }
+                write!(f, "}}")
+            }),
+            Value::Tuple(values) => {
+                write!(f, "(")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value.to_nested_string())?;
+                }
+                // `(1,)`, as a one-value tuple is written
+                if values.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Value::Range(range) => {
+                let operator = if range.inclusive { "..=" } else { ".." };
+                write!(f, "{}{}{}", range.start, operator, range.end)?;
+                if range.step != 1 {
+                    write!(f, " step {}", range.step)?;
+                }
+                Ok(())
+            }
+            Value::Function(func) => match &func.name {
+                Some(name) => write!(f, "<fn {}>", name),
+                None => write!(f, "<fn>"),
+            },
+            Value::NativeFunction(native) => write!(f, "<native fn {}>", native.name),
+            Value::StructDef(def) => write!(f, "<struct {}>", def.name),
+            Value::Struct(shared) => {
+                let instance = shared.borrow();
+                display_once(f, shared, &format!("{} {{ ... }}", instance.def.name), |f| {
+                    write!(f, "{} {{ ", instance.def.name)?;
+                    for (i, (field, value)) in instance.def.fields.iter().zip(&instance.values).enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}: {}", field, value.to_nested_string())?;
+                    }
+                    write!(f, " }}")
+                })
+            }
+            Value::EnumDef(def) => write!(f, "<enum {}>", def.name),
+            Value::Module(module) => write!(f, "<module {}>", module.name),
+            Value::Generator(generator) => write!(f, "<generator {}>", generator.borrow().display_name()),
+            Value::Enum(value) => {
+                write!(f, "{}::{}", value.def.name, value.def.variants[value.variant].0)?;
+                if !value.values.is_empty() {
+                    write!(f, "(")?;
+                    for (i, value) in value.values.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", value.to_nested_string())?;
+                    }
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
         }
     }
-}
\ No newline at end of file
+}